@@ -0,0 +1,225 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+use egui::CursorIcon;
+use egui_sdl2_event::FusedCursor;
+use sdl2::mouse::{Cursor, SystemCursor};
+use sdl2::pixels::PixelFormatEnum;
+use sdl2::surface::Surface;
+use sdl2::video::Window;
+
+// SDL only has undirected resize cursors, so each edge and corner of egui's directional resize
+// icons maps to the axis it resizes along.
+pub fn system_cursor_for(cursor_icon: CursorIcon) -> SystemCursor {
+    match cursor_icon {
+        CursorIcon::Crosshair => SystemCursor::Crosshair,
+        CursorIcon::Default => SystemCursor::Arrow,
+        CursorIcon::Grab => SystemCursor::Hand,
+        CursorIcon::Grabbing => SystemCursor::SizeAll,
+        CursorIcon::Move | CursorIcon::AllScroll => SystemCursor::SizeAll,
+        CursorIcon::PointingHand => SystemCursor::Hand,
+        CursorIcon::ResizeHorizontal
+        | CursorIcon::ResizeEast
+        | CursorIcon::ResizeWest
+        | CursorIcon::ResizeColumn => SystemCursor::SizeWE,
+        CursorIcon::ResizeVertical
+        | CursorIcon::ResizeNorth
+        | CursorIcon::ResizeSouth
+        | CursorIcon::ResizeRow => SystemCursor::SizeNS,
+        CursorIcon::ResizeNwSe | CursorIcon::ResizeNorthWest | CursorIcon::ResizeSouthEast => {
+            SystemCursor::SizeNWSE
+        }
+        CursorIcon::ResizeNeSw | CursorIcon::ResizeNorthEast | CursorIcon::ResizeSouthWest => {
+            SystemCursor::SizeNESW
+        }
+        CursorIcon::Text | CursorIcon::VerticalText => SystemCursor::IBeam,
+        CursorIcon::NotAllowed | CursorIcon::NoDrop => SystemCursor::No,
+        CursorIcon::Wait => SystemCursor::Wait,
+        CursorIcon::Progress => SystemCursor::WaitArrow,
+        //There doesn't seem to be a suitable SDL equivalent...
+        _ => SystemCursor::Arrow,
+    }
+}
+
+// Not every platform provides every system cursor. When one can't be created the arrow is shown
+// instead; `fused.icon` still records the requested icon so the failure is logged only once
+// rather than on every frame egui asks for it. Cursors are made by `create`, normally
+// `Cursor::from_system`.
+pub fn set_cursor(
+    fused: &mut FusedCursor,
+    cursor_icon: CursorIcon,
+    mut create: impl FnMut(SystemCursor) -> Result<Cursor, String>,
+) {
+    let icon = system_cursor_for(cursor_icon);
+    if icon == fused.icon {
+        return;
+    }
+    fused.icon = icon;
+    let cursor = create(icon).or_else(|e| {
+        eprintln!("Cannot create SDL system cursor {:?}, using the arrow: {}", icon, e);
+        create(SystemCursor::Arrow)
+    });
+    match cursor {
+        Ok(cursor) => {
+            fused.cursor = cursor;
+            fused.cursor.set();
+        }
+        Err(e) => eprintln!("Cannot create SDL arrow cursor: {}", e),
+    }
+}
+
+// Keeps the pointer inside the window while `confine` is set, e.g. for the duration of a drag.
+// This is a plain grab: the cursor stays visible and egui keeps getting absolute positions.
+// SDL's relative mouse mode grabs implicitly as well, but hides the cursor and only reports
+// deltas that egui can't use; while the app has it enabled, releasing the grab here has no effect.
+#[allow(dead_code)]
+pub fn confine_cursor(window: &mut Window, confine: bool) {
+    if window.grab() != confine {
+        window.set_grab(confine);
+    }
+}
+
+// One resolution of a custom cursor image, RGBA8 with no row padding.
+pub struct CursorImage {
+    pub rgba: Vec<u8>,
+    pub width: u32,
+    pub height: u32,
+    // Pixels per point the image was drawn for, e.g. 2.0 for an @2x image.
+    pub scale: f32,
+}
+
+// Picks the smallest image drawn for at least `pixels_per_point`, or the largest one if all are
+// smaller, so the cursor isn't upscaled on HiDPI displays when a sharper image exists.
+pub fn best_cursor_image(images: &[CursorImage], pixels_per_point: f32) -> Option<&CursorImage> {
+    let sharp_enough = images
+        .iter()
+        .filter(|image| image.scale >= pixels_per_point)
+        .min_by(|a, b| a.scale.total_cmp(&b.scale));
+    sharp_enough.or_else(|| images.iter().max_by(|a, b| a.scale.total_cmp(&b.scale)))
+}
+
+// `hotspot` is in points from the top left of the image and is scaled to the chosen image, so the
+// click point stays on the same spot of the artwork at any resolution.
+pub fn create_custom_cursor(
+    images: &[CursorImage],
+    hotspot: (f32, f32),
+    pixels_per_point: f32,
+) -> Result<Cursor, String> {
+    let image = best_cursor_image(images, pixels_per_point).ok_or("No cursor images given")?;
+    let mut rgba = image.rgba.clone();
+    let surface = Surface::from_data(&mut rgba, image.width, image.height, image.width * 4, PixelFormatEnum::RGBA32)?;
+    let hot_x = (hotspot.0 * image.scale).round() as i32;
+    let hot_y = (hotspot.1 * image.scale).round() as i32;
+    Cursor::from_surface(surface, hot_x, hot_y)
+}
+
+// The app's own cursors, created once and kept by an app-chosen id. Registering them all at
+// startup with `register_cursors` moves the image conversion and SDL cursor creation out of the
+// frame in which each cursor is first shown. Every cursor keeps its image alive in SDL (and
+// usually in the OS) until the cache is dropped, so register the images a tool set needs, not a
+// whole catalogue of large HiDPI variants.
+pub struct CustomCursors<Id> {
+    cursors: HashMap<Id, Cursor>,
+}
+
+#[allow(dead_code)]
+impl<Id: Eq + Hash> CustomCursors<Id> {
+    pub fn new() -> CustomCursors<Id> {
+        CustomCursors {
+            cursors: HashMap::new(),
+        }
+    }
+
+    // Replaces any cursor already registered under `id`. The image is picked for
+    // `pixels_per_point` now, so register again after the scale changes.
+    pub fn register(
+        &mut self,
+        id: Id,
+        images: &[CursorImage],
+        hotspot: (f32, f32),
+        pixels_per_point: f32,
+    ) -> Result<(), String> {
+        let cursor = create_custom_cursor(images, hotspot, pixels_per_point)?;
+        self.cursors.insert(id, cursor);
+        Ok(())
+    }
+
+    // Stops at the first cursor that can't be created; the ones before it stay registered.
+    pub fn register_cursors(
+        &mut self,
+        cursors: impl IntoIterator<Item = (Id, Vec<CursorImage>, (f32, f32))>,
+        pixels_per_point: f32,
+    ) -> Result<(), String> {
+        for (id, images, hotspot) in cursors {
+            self.register(id, &images, hotspot, pixels_per_point)?;
+        }
+        Ok(())
+    }
+
+    // Returns false if nothing is registered under `id`. egui's next `process_output` only
+    // changes the cursor again once egui asks for a different system icon.
+    pub fn show(&self, id: &Id) -> bool {
+        match self.cursors.get(id) {
+            Some(cursor) => {
+                cursor.set();
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resize_icons_map_to_their_axis() {
+        let cases = [
+            (CursorIcon::ResizeHorizontal, SystemCursor::SizeWE),
+            (CursorIcon::ResizeEast, SystemCursor::SizeWE),
+            (CursorIcon::ResizeWest, SystemCursor::SizeWE),
+            (CursorIcon::ResizeVertical, SystemCursor::SizeNS),
+            (CursorIcon::ResizeNorth, SystemCursor::SizeNS),
+            (CursorIcon::ResizeSouth, SystemCursor::SizeNS),
+            (CursorIcon::ResizeNwSe, SystemCursor::SizeNWSE),
+            (CursorIcon::ResizeNorthWest, SystemCursor::SizeNWSE),
+            (CursorIcon::ResizeSouthEast, SystemCursor::SizeNWSE),
+            (CursorIcon::ResizeNeSw, SystemCursor::SizeNESW),
+            (CursorIcon::ResizeNorthEast, SystemCursor::SizeNESW),
+            (CursorIcon::ResizeSouthWest, SystemCursor::SizeNESW),
+        ];
+        for (icon, expected) in cases {
+            assert_eq!(system_cursor_for(icon), expected, "{:?}", icon);
+        }
+    }
+
+    #[test]
+    fn busy_icons_map_to_wait_cursors() {
+        assert_eq!(system_cursor_for(CursorIcon::Wait), SystemCursor::Wait);
+        assert_eq!(system_cursor_for(CursorIcon::Progress), SystemCursor::WaitArrow);
+    }
+
+    #[test]
+    fn missing_system_cursor_falls_back_to_the_arrow() {
+        crate::input::tests::with_window(|_| {
+            let mut fused = FusedCursor::new();
+            let mut requested = Vec::new();
+            set_cursor(&mut fused, CursorIcon::ResizeHorizontal, |icon| {
+                requested.push(icon);
+                match icon {
+                    SystemCursor::SizeWE => Err("not supported".to_string()),
+                    _ => Cursor::from_system(icon),
+                }
+            });
+            assert_eq!(requested, vec![SystemCursor::SizeWE, SystemCursor::Arrow]);
+            assert_eq!(fused.icon, SystemCursor::SizeWE);
+
+            requested.clear();
+            set_cursor(&mut fused, CursorIcon::ResizeEast, |icon| {
+                requested.push(icon);
+                Cursor::from_system(icon)
+            });
+            assert!(requested.is_empty());
+        });
+    }
+}
@@ -33,6 +33,11 @@ impl FrameTimer {
         self.stop = self.time_now();
     }
 
+    // Milliseconds since `time_start`.
+    pub fn elapsed_ms(&self) -> u32 {
+        self.time_now() - self.start
+    }
+
     pub fn delta(&self) -> f32 {
         self.delta
     }
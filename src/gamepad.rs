@@ -0,0 +1,59 @@
+use std::time::Duration;
+use sdl2::controller::GameController;
+use sdl2::event::Event;
+use sdl2::GameControllerSubsystem;
+
+// Keeps a game controller open for every pad SDL reports, following hotplug events.
+// SDL sends `ControllerDeviceAdded` for pads already connected at startup too, so feeding
+// every event through `on_event` is enough to pick those up.
+pub struct Gamepads {
+    subsystem: GameControllerSubsystem,
+    controllers: Vec<GameController>,
+}
+
+impl Gamepads {
+    pub fn new(subsystem: GameControllerSubsystem) -> Gamepads {
+        Gamepads {
+            subsystem,
+            controllers: Vec::new(),
+        }
+    }
+
+    pub fn on_event(&mut self, event: &Event) {
+        match event {
+            // `which` is the joystick device index here...
+            Event::ControllerDeviceAdded { which, .. } => match self.subsystem.open(*which) {
+                Ok(controller) => {
+                    if !self.controllers.iter().any(|c| c.instance_id() == controller.instance_id()) {
+                        self.controllers.push(controller);
+                    }
+                }
+                Err(e) => eprintln!("Cannot open game controller {}: {}", which, e),
+            },
+            // ...and the instance id here.
+            Event::ControllerDeviceRemoved { which, .. } => {
+                self.controllers.retain(|c| c.instance_id() != *which);
+            }
+            _ => {}
+        }
+    }
+
+    pub fn connected(&self) -> &[GameController] {
+        &self.controllers
+    }
+
+    pub fn connected_mut(&mut self) -> &mut [GameController] {
+        &mut self.controllers
+    }
+}
+
+// Starts rumbling with the low (left) and high (right) frequency motors at 0.0..=1.0 of full
+// strength, replacing any rumble still running. E.g. call it when an egui response is clicked
+// or changed. Pads without rumble motors return an error.
+pub fn rumble(controller: &mut GameController, low: f32, high: f32, duration: Duration) -> Result<(), String> {
+    let strength = |value: f32| (value.clamp(0.0, 1.0) * u16::MAX as f32) as u16;
+    let duration_ms = duration.as_millis().min(u32::MAX as u128) as u32;
+    controller
+        .set_rumble(strength(low), strength(high), duration_ms)
+        .map_err(|e| format!("Cannot rumble game controller {}: {}", controller.name(), e))
+}
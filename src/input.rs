@@ -0,0 +1,2119 @@
+use std::collections::HashSet;
+use egui::{Context, FullOutput, Key, Modifiers, PlatformOutput, PointerButton, RawInput};
+use egui_sdl2_event::EguiSDL2State;
+use sdl2::event::{Event, WindowEvent};
+use sdl2::keyboard::{Keycode, Mod};
+use sdl2::mouse::{Cursor, MouseButton, MouseState, MouseWheelDirection};
+use sdl2::video::Window;
+use sdl2::TimerSubsystem;
+use crate::cursor;
+use crate::keys;
+use crate::keys::ModifierSides;
+use crate::touch::{TouchState, SDL_TOUCH_MOUSEID};
+
+// What egui made of a translated event, in the style of egui-winit.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct EventResponse {
+    // egui wants this event for itself, so the app should not act on it.
+    pub consumed: bool,
+    // The event changed egui's input, so a new frame should be run.
+    pub repaint: bool,
+}
+
+impl EventResponse {
+    // Responses fold by OR-ing: consumed if any event was consumed, repaint if any asked for it.
+    pub fn merge(self, other: EventResponse) -> EventResponse {
+        EventResponse {
+            consumed: self.consumed || other.consumed,
+            repaint: self.repaint || other.repaint,
+        }
+    }
+}
+
+// Which wheel events reach egui. Either way `EventResponse::consumed` reports whether egui
+// wanted the pointer, so the app can decide whether to scroll its own scene too.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ScrollPolicy {
+    // Every wheel event goes to egui, even while the pointer is over the scene.
+    #[allow(dead_code)]
+    Always,
+    // Only while egui wants the pointer, i.e. it is over an egui area or dragging. Otherwise the
+    // wheel is left to the scene and egui doesn't scroll or zoom.
+    WhenPointerWanted,
+}
+
+// What a wheel notch does while Alt (and not Ctrl) is held. egui 0.18's `Scroll` event carries no
+// modifiers of its own; egui reads them from the input state, so apps can still tell Alt+scroll
+// apart when it is forwarded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AltScroll {
+    Scroll,
+    // Drop the scroll so the app can use Alt+wheel for its own action, like adjusting a value.
+    Suppress,
+    // Zoom like Ctrl+wheel does.
+    Zoom,
+}
+
+pub struct InputConfig {
+    // Points scrolled per step of a stepped mouse wheel. Precise (touchpad) scroll sources would
+    // want their own multiplier, but the SDL 2.0 bindings this example uses only report integer
+    // wheel deltas without the precise values, so every wheel event is treated as stepped.
+    pub points_per_wheel_notch: f32,
+    // Bounds for the scale handed to egui, so a runaway zoom or a bogus DPI value can't produce a
+    // degenerate UI or oversized textures.
+    pub min_pixels_per_point: f32,
+    pub max_pixels_per_point: f32,
+    // Translate SDL finger events into egui touches (and pointer events for the first finger).
+    // SDL also synthesizes mouse events from touch by default; those are dropped in this mode so
+    // a tap isn't reported twice.
+    pub touch_input: bool,
+    // Stop advancing the time reported to egui while the window is unfocused, so animations
+    // freeze instead of running in the background. Off by default since some apps animate while
+    // unfocused on purpose. Time resumes from where it stopped once focus returns.
+    pub pause_time_when_unfocused: bool,
+    // Keys that always go to the app and are never shown to egui, even while a text field has
+    // focus, e.g. global hotkeys. The text a printable app key types is dropped along with it.
+    // Any other key goes to egui, and `EventResponse::consumed` tells the app whether egui
+    // wanted it. Empty by default.
+    pub app_keys: Vec<Keycode>,
+    // While a text field has focus, don't report `Key` events for keys that also type a
+    // character (letters, digits, space) unless a shortcut modifier is held. The character still
+    // arrives as text, but egui won't also treat the keystroke as a key press.
+    pub suppress_printable_keys_while_typing: bool,
+    // Treat `points_per_wheel_notch` as physical pixels, dividing by `pixels_per_point`, so a
+    // notch moves content the same number of pixels at any scale. Off by default, where a notch
+    // scrolls the same number of points.
+    pub scroll_in_pixels: bool,
+    // Modifiers that, held together with Ctrl, turn a wheel notch into a finer zoom step, e.g.
+    // `Mod::LSHIFTMOD | Mod::RSHIFTMOD` for Ctrl+Shift. Empty by default, where every Ctrl+wheel
+    // notch is one coarse step. The fine step is the coarse one scaled by `fine_zoom_scale`.
+    pub fine_zoom_modifiers: Mod,
+    pub fine_zoom_scale: f32,
+    // Turn a press of the Menu (Application) key into a secondary click at the pointer, which
+    // opens the context menu of the widget under it. egui 0.18 has no Menu key and the platform
+    // side doesn't know where the focused widget is, so the pointer position is the best target.
+    pub menu_key_opens_context_menu: bool,
+    pub scroll_policy: ScrollPolicy,
+    // Pin `pixels_per_point` to 1.0 whatever scale the app or the display asks for, so one egui
+    // point is one pixel of the window, e.g. for pixel-art UIs. Text and widgets will look small
+    // on HiDPI displays in this mode.
+    pub pixel_perfect: bool,
+    // Sum wheel scrolls that follow each other within a frame, with no other egui event in between,
+    // into one `Scroll` event. Off by default, where each SDL wheel event becomes its own `Scroll`;
+    // the total distance is the same either way.
+    pub coalesce_scroll: bool,
+    // With `touch_input`, keep only the latest position of each finger's motion within a frame
+    // instead of one `Touch` move per SDL event. Presses and releases are always kept. Off by
+    // default.
+    pub coalesce_touch_motion: bool,
+    // With `touch_input`, how far in pixels the first finger may move before the pointer starts
+    // following it, so finger jitter during a tap doesn't turn it into a drag.
+    pub touch_tap_threshold: f32,
+    // Send every input event to egui and report all pointer and keyboard events as consumed,
+    // whether or not egui wants them, e.g. while an always-on debug console overlays a game.
+    // `app_keys` and `scroll_policy` are bypassed too, so the app's own input is effectively off
+    // for as long as this is set. Off by default.
+    pub capture_all_input: bool,
+    // SDL's wheel deltas already follow the OS scroll direction setting on every platform: with
+    // natural scrolling they arrive inverted and, on macOS, are marked
+    // `MouseWheelDirection::Flipped`. Passing them on as they are keeps scrolling the way the user
+    // configured it. Set this to undo the inversion for marked events instead, e.g. for a map
+    // that should always zoom the same way. Other platforms, Windows precision touchpads
+    // included, invert without marking their events in SDL 2.0, so it has no effect there.
+    pub ignore_natural_scrolling: bool,
+    // How many of the most recently translated egui events `recent_events` keeps, e.g. for an
+    // in-app input debugger. 0 turns the log off.
+    pub recent_events_capacity: usize,
+    // Points scrolled per unit of joystick ball motion, with the `gamepad` feature. Balls report
+    // many small steps, hence the low default.
+    pub joy_ball_points_per_unit: f32,
+    pub alt_scroll: AltScroll,
+}
+
+impl Default for InputConfig {
+    fn default() -> Self {
+        InputConfig {
+            points_per_wheel_notch: 8.0,
+            min_pixels_per_point: 0.25,
+            max_pixels_per_point: 5.0,
+            touch_input: false,
+            pause_time_when_unfocused: false,
+            app_keys: Vec::new(),
+            suppress_printable_keys_while_typing: false,
+            scroll_in_pixels: false,
+            fine_zoom_modifiers: Mod::empty(),
+            fine_zoom_scale: 0.25,
+            menu_key_opens_context_menu: false,
+            scroll_policy: ScrollPolicy::WhenPointerWanted,
+            pixel_perfect: false,
+            coalesce_scroll: false,
+            coalesce_touch_motion: false,
+            touch_tap_threshold: 8.0,
+            capture_all_input: false,
+            ignore_natural_scrolling: false,
+            recent_events_capacity: 32,
+            joy_ball_points_per_unit: 0.5,
+            alt_scroll: AltScroll::Scroll,
+        }
+    }
+}
+
+impl InputConfig {
+    fn clamp_pixels_per_point(&self, pixels_per_point: f32) -> f32 {
+        if self.pixel_perfect {
+            1.0
+        } else {
+            pixels_per_point.clamp(self.min_pixels_per_point, self.max_pixels_per_point)
+        }
+    }
+}
+
+// Same button mapping as egui-sdl2-event uses.
+pub fn pointer_button(mouse_btn: MouseButton) -> Option<PointerButton> {
+    match mouse_btn {
+        MouseButton::Left => Some(PointerButton::Primary),
+        MouseButton::Middle => Some(PointerButton::Middle),
+        MouseButton::Right => Some(PointerButton::Secondary),
+        _ => None,
+    }
+}
+
+// Same interpretation as egui-sdl2-event uses for its key events.
+pub fn modifiers_from_sdl(keymod: Mod) -> Modifiers {
+    Modifiers {
+        alt: keymod.intersects(Mod::LALTMOD | Mod::RALTMOD),
+        ctrl: keymod.intersects(Mod::LCTRLMOD | Mod::RCTRLMOD),
+        shift: keymod.intersects(Mod::LSHIFTMOD | Mod::RSHIFTMOD),
+        mac_cmd: keymod.contains(Mod::LGUIMOD),
+        command: keymod.intersects(Mod::LCTRLMOD | Mod::LGUIMOD),
+    }
+}
+
+// The platform side effects `InputState::run_frame` applied for a frame.
+#[cfg(feature = "test-harness")]
+#[derive(Clone, Debug, PartialEq)]
+pub struct AppliedOutput {
+    // Text set as the SDL clipboard contents, if egui copied any.
+    pub clipboard_text: Option<String>,
+    // The SDL system cursor requested for egui's cursor icon.
+    pub system_cursor: sdl2::mouse::SystemCursor,
+    // SDL's text input rect in window coordinates, set while a text field has focus.
+    pub text_input_rect: Option<sdl2::rect::Rect>,
+}
+
+// How a translated event is reported in its `EventResponse`.
+enum Translated {
+    Pointer,
+    Keyboard,
+    Other,
+    Ignored,
+}
+
+// Wraps `EguiSDL2State` with the extra event and output handling the example needs.
+pub struct InputState {
+    pub egui_state: EguiSDL2State,
+    pub config: InputConfig,
+    screen_keyboard_requested: bool,
+    last_sdl_ticks: Option<u32>,
+    // Logical window size, which SDL's mouse coordinates are in, and the drawable size in pixels.
+    window_size: (u32, u32),
+    drawable_size: (u32, u32),
+    // Drawable pixels per logical pixel: 1.0 unless the window is HiDPI-aware on a HiDPI display.
+    drawable_scale: f32,
+    text_input_rect: Option<sdl2::rect::Rect>,
+    visible: bool,
+    touch: TouchState,
+    held_buttons: Vec<PointerButton>,
+    focused: bool,
+    // Time spent unfocused while `pause_time_when_unfocused` is set, subtracted from the clock.
+    paused_time: f64,
+    last_time: Option<f64>,
+    copied_text: Option<String>,
+    text_field_focused: bool,
+    render_reset: bool,
+    // Requested by `override_pixels_per_point_once`, and the one applied to the current frame.
+    next_frame_pixels_per_point: Option<f32>,
+    frame_pixels_per_point: Option<f32>,
+    modifier_sides: ModifierSides,
+    // Display refresh interval from `sync_refresh_rate`, reported as `predicted_dt` instead of
+    // the measured frame delta.
+    refresh_interval: Option<f32>,
+    pointer_over_area: bool,
+    caps_lock_held: bool,
+    // An IME composition is in progress: SDL reported non-empty `TextEditing` text that hasn't
+    // been committed as `TextInput` yet.
+    composing: bool,
+    // The time handed to egui by the last `take_raw_input`.
+    taken_time: Option<f64>,
+    // Summed SDL `xrel`/`yrel` since the last `take_raw_input`, and the sum that went into it.
+    pending_relative_motion: egui::Vec2,
+    relative_motion: egui::Vec2,
+    recent_events: Vec<egui::Event>,
+    // Where `center_cursor` warped the mouse until SDL's motion event for it arrives.
+    warp_target: Option<(i32, i32)>,
+    // Set by `set_screen_rect`: the part of the window, in points, egui is confined to.
+    custom_screen_rect: Option<egui::Rect>,
+    held_keys: HashSet<Key>,
+    // An app key that types was just pressed, so the `TextInput` SDL sends after it is dropped
+    // too. Cleared by the next key press, for when no text follows (e.g. with Ctrl held).
+    app_key_text_pending: bool,
+}
+
+impl InputState {
+    pub fn new(width: u32, height: u32, dpi_scaling: f32) -> InputState {
+        InputState::with_config(width, height, dpi_scaling, InputConfig::default())
+    }
+
+    pub fn with_config(width: u32, height: u32, dpi_scaling: f32, config: InputConfig) -> InputState {
+        let dpi_scaling = config.clamp_pixels_per_point(dpi_scaling);
+        InputState {
+            egui_state: EguiSDL2State::new(width, height, dpi_scaling),
+            config,
+            screen_keyboard_requested: false,
+            last_sdl_ticks: None,
+            window_size: (width, height),
+            drawable_size: (width, height),
+            drawable_scale: 1.0,
+            text_input_rect: None,
+            visible: true,
+            touch: TouchState::new(),
+            held_buttons: Vec::new(),
+            focused: true,
+            paused_time: 0.0,
+            last_time: None,
+            copied_text: None,
+            text_field_focused: false,
+            render_reset: false,
+            next_frame_pixels_per_point: None,
+            frame_pixels_per_point: None,
+            modifier_sides: ModifierSides::default(),
+            refresh_interval: None,
+            pointer_over_area: false,
+            caps_lock_held: false,
+            composing: false,
+            taken_time: None,
+            pending_relative_motion: egui::Vec2::ZERO,
+            relative_motion: egui::Vec2::ZERO,
+            recent_events: Vec::new(),
+            warp_target: None,
+            custom_screen_rect: None,
+            held_keys: HashSet::new(),
+            app_key_text_pending: false,
+        }
+    }
+
+    pub fn on_event(&mut self, egui_ctx: &Context, window: &Window, event: &Event) -> EventResponse {
+        // Events without a window id (touch, device and app events) are taken to be for this window.
+        if let Some(window_id) = event.get_window_id() {
+            if window_id != window.id() {
+                return EventResponse::default();
+            }
+        }
+        let capture = self.config.capture_all_input;
+        if let Event::MouseWheel { .. } = event {
+            let policy = self.config.scroll_policy;
+            if !capture && policy == ScrollPolicy::WhenPointerWanted && !egui_ctx.wants_pointer_input() {
+                return EventResponse::default();
+            }
+        }
+        let first_new = self.egui_state.raw_input.events.len();
+        let translated = self.translate(window, event);
+        // Touch translation offsets its own positions, coalesced moves included.
+        if !matches!(event, Event::FingerDown { .. } | Event::FingerUp { .. } | Event::FingerMotion { .. }) {
+            self.offset_new_events(first_new);
+        }
+        self.log_recent_events(first_new);
+        match translated {
+            Translated::Pointer => EventResponse {
+                consumed: capture || egui_ctx.wants_pointer_input(),
+                repaint: true,
+            },
+            Translated::Keyboard => EventResponse {
+                consumed: capture || egui_ctx.wants_keyboard_input(),
+                repaint: true,
+            },
+            Translated::Other => EventResponse {
+                consumed: false,
+                repaint: true,
+            },
+            Translated::Ignored => EventResponse::default(),
+        }
+    }
+
+    // Confines egui to `rect`, in window points, e.g. when it is drawn into a panel of a larger
+    // scene. egui gets a screen rect of the same size at the origin, and pointer and touch
+    // positions are translated to be relative to `rect.min`, so the app has to paint egui's
+    // output with the same offset. The screen rect is no longer derived from the window size
+    // until `reset_screen_rect`; window resizes and DPI changes only scale the positions.
+    #[allow(dead_code)]
+    pub fn set_screen_rect(&mut self, rect: egui::Rect) {
+        self.custom_screen_rect = Some(rect);
+        self.egui_state.raw_input.screen_rect = Some(egui::Rect::from_min_size(egui::Pos2::ZERO, rect.size()));
+    }
+
+    #[allow(dead_code)]
+    pub fn reset_screen_rect(&mut self) {
+        self.custom_screen_rect = None;
+        self.egui_state.raw_input.screen_rect = Some(self.screen_rect());
+    }
+
+    fn offset_new_events(&mut self, first_new: usize) {
+        let offset = match self.custom_screen_rect {
+            Some(rect) => rect.min.to_vec2(),
+            None => return,
+        };
+        let new_events = self.egui_state.raw_input.events.get_mut(first_new..).unwrap_or_default();
+        for event in new_events {
+            match event {
+                egui::Event::PointerMoved(pos)
+                | egui::Event::PointerButton { pos, .. }
+                | egui::Event::Touch { pos, .. } => *pos -= offset,
+                _ => {}
+            }
+        }
+    }
+
+    // Copies the events `translate` just pushed. Events merged into earlier ones by coalescing
+    // aren't logged again.
+    fn log_recent_events(&mut self, first_new: usize) {
+        let capacity = self.config.recent_events_capacity;
+        let new_events = self.egui_state.raw_input.events.get(first_new..).unwrap_or_default();
+        self.recent_events.extend(new_events.iter().cloned());
+        if self.recent_events.len() > capacity {
+            let excess = self.recent_events.len() - capacity;
+            self.recent_events.drain(..excess);
+        }
+    }
+
+    // The last `recent_events_capacity` egui events translated from SDL, oldest first. Unlike
+    // `raw_input.events` it isn't emptied when egui takes the input.
+    #[allow(dead_code)]
+    pub fn recent_events(&self) -> &[egui::Event] {
+        &self.recent_events
+    }
+
+    // Every SDL event is listed explicitly, without a catch-all, so an event can't end up ignored
+    // by accident and a new SDL event type has to be classified before this compiles. sdl2 only
+    // has `ControllerSensorUpdated` with its `hidapi` feature, so enable sdl2's hidapi through
+    // this crate's `hidapi` feature; turned on any other way the variant goes unmatched.
+    fn translate(&mut self, window: &Window, event: &Event) -> Translated {
+        match event {
+            Event::Window { win_event, .. } => {
+                match win_event {
+                    // During a fast resize the sizes carried by queued events are already stale
+                    // and may disagree with each other; the window's current size is one snapshot.
+                    WindowEvent::Resized(..) | WindowEvent::SizeChanged(..) => self.sync_geometry(window),
+                    WindowEvent::Hidden | WindowEvent::Minimized => self.visible = false,
+                    WindowEvent::Shown | WindowEvent::Restored | WindowEvent::Maximized => self.visible = true,
+                    // SDL can repeat a focus event without the focus changing in between; only a
+                    // real change releases buttons or counts as new input.
+                    WindowEvent::FocusLost | WindowEvent::FocusGained
+                        if self.focused == (*win_event == WindowEvent::FocusGained) =>
+                    {
+                        return Translated::Ignored
+                    }
+                    WindowEvent::FocusLost => {
+                        self.focused = false;
+                        // The key-up goes to whichever window has focus by then.
+                        self.caps_lock_held = false;
+                        self.release_held_keys();
+                        self.release_held_buttons();
+                    }
+                    WindowEvent::FocusGained => self.focused = true,
+                    // The window may have landed on a display with a different refresh rate.
+                    // SDL 2.0 has no separate display-changed window event, so check on every move.
+                    WindowEvent::Moved(..) => self.sync_refresh_rate(window),
+                    _ => {}
+                }
+                Translated::Other
+            }
+
+            // Only the first motion after `center_cursor` can be the warp's; any motion clears it.
+            Event::MouseMotion { x, y, .. } if self.warp_target.take() == Some((*x, *y)) => Translated::Ignored,
+            Event::MouseMotion { which: SDL_TOUCH_MOUSEID, .. } if self.config.touch_input => Translated::Ignored,
+            Event::MouseMotion { x, y, xrel, yrel, mousestate, .. } => {
+                self.pending_relative_motion += egui::vec2(*xrel as f32, *yrel as f32);
+                self.reconcile_held_buttons(mousestate);
+                if self.held_buttons.is_empty() {
+                    self.egui_state.sdl2_input_to_egui(window, event);
+                } else {
+                    // SDL keeps reporting motion past the window edge while a button is held.
+                    // Keep forwarding it so drags don't stall at the edge, but clamped to the window.
+                    let pos = self.pointer_position(*x, *y);
+                    self.egui_state.mouse_pointer_position = pos;
+                    self.egui_state.raw_input.events.push(egui::Event::PointerMoved(pos));
+                }
+                Translated::Pointer
+            }
+            Event::MouseButtonDown { which: SDL_TOUCH_MOUSEID, .. }
+            | Event::MouseButtonUp { which: SDL_TOUCH_MOUSEID, .. }
+                if self.config.touch_input =>
+            {
+                Translated::Ignored
+            }
+            // egui-sdl2-event reports buttons at the position of the last motion event, which is
+            // stale if the pointer was warped or no motion preceded the click. Take the position
+            // from the button event itself, kept as a float so a fractional scale gives
+            // sub-point precision, and clamped like drag motion for a release outside the window.
+            Event::MouseButtonDown { mouse_btn, x, y, .. } => {
+                self.egui_state.mouse_pointer_position = self.pointer_position(*x, *y);
+                self.egui_state.sdl2_input_to_egui(window, event);
+                if let Some(button) = pointer_button(*mouse_btn) {
+                    if !self.held_buttons.contains(&button) {
+                        self.held_buttons.push(button);
+                    }
+                }
+                Translated::Pointer
+            }
+            Event::MouseButtonUp { mouse_btn, x, y, .. } => {
+                self.egui_state.mouse_pointer_position = self.pointer_position(*x, *y);
+                self.egui_state.sdl2_input_to_egui(window, event);
+                if let Some(button) = pointer_button(*mouse_btn) {
+                    self.held_buttons.retain(|held| *held != button);
+                }
+                Translated::Pointer
+            }
+            Event::MouseWheel { x, y, direction, .. } => {
+                let (x, y) = if self.config.ignore_natural_scrolling && *direction == MouseWheelDirection::Flipped {
+                    (-*x, -*y)
+                } else {
+                    (*x, *y)
+                };
+                self.translate_wheel(window, x, y);
+                Translated::Pointer
+            }
+            // Trackballs built into joysticks. SDL only reports them for opened joysticks, which the
+            // `gamepad` feature takes care of for game controllers.
+            Event::JoyBallMotion { xrel, yrel, .. } if cfg!(feature = "gamepad") => {
+                let points = self.config.joy_ball_points_per_unit;
+                let delta = egui::vec2(-(*xrel as f32), -(*yrel as f32)) * points;
+                self.egui_state.raw_input.events.push(egui::Event::Scroll(delta));
+                Translated::Pointer
+            }
+            Event::JoyBallMotion { .. } => Translated::Ignored,
+            Event::FingerDown { .. } | Event::FingerUp { .. } | Event::FingerMotion { .. } => {
+                if self.config.touch_input && self.translate_touch(event) {
+                    Translated::Pointer
+                } else {
+                    Translated::Ignored
+                }
+            }
+
+            Event::KeyDown { keycode: Some(keycode), .. } | Event::KeyUp { keycode: Some(keycode), .. }
+                if !self.config.capture_all_input && self.config.app_keys.contains(keycode) =>
+            {
+                if let Event::KeyDown { .. } = event {
+                    self.app_key_text_pending = keys::types_text(*keycode);
+                }
+                Translated::Ignored
+            }
+            Event::KeyDown { keycode, keymod, .. } => {
+                self.app_key_text_pending = false;
+                self.translate_key(window, *keycode, *keymod, true);
+                Translated::Keyboard
+            }
+            Event::KeyUp { keycode, keymod, .. } => {
+                self.translate_key(window, *keycode, *keymod, false);
+                Translated::Keyboard
+            }
+            // As in egui-winit, a character typed with Ctrl or Cmd held is a shortcut, not text:
+            // its `Key` event is still sent, but the text is dropped so Ctrl+C doesn't also type
+            // a 'c' on platforms where SDL reports it. Windows reports AltGr as Ctrl+Alt, so that
+            // combination still types.
+            Event::TextInput { .. } if std::mem::take(&mut self.app_key_text_pending) => Translated::Ignored,
+            Event::TextInput { .. } => {
+                self.composing = false;
+                let modifiers = self.egui_state.modifiers;
+                let shortcut = (modifiers.ctrl || modifiers.mac_cmd) && !(modifiers.ctrl && modifiers.alt);
+                if !shortcut {
+                    self.egui_state.sdl2_input_to_egui(window, event);
+                }
+                Translated::Keyboard
+            }
+            Event::TextEditing { text, .. } => {
+                self.composing = !text.is_empty();
+                Translated::Keyboard
+            }
+
+            // Only reaches egui for one frame: `RawInput::take` moves `dropped_files` out.
+            // SDL 2.0 can't tell when files are dragged over the window, so `hovered_files`
+            // stays empty.
+            Event::DropFile { filename, .. } => {
+                self.egui_state.raw_input.dropped_files.push(egui::DroppedFile {
+                    path: Some(filename.into()),
+                    name: filename.clone(),
+                    ..Default::default()
+                });
+                Translated::Other
+            }
+
+            Event::RenderTargetsReset { .. } | Event::RenderDeviceReset { .. } => {
+                self.render_reset = true;
+                Translated::Other
+            }
+            // sdl2 0.35 has no variant for SDL_KEYMAPCHANGED.
+            Event::Unknown { type_, .. } => {
+                if *type_ == sdl2::sys::SDL_EventType::SDL_KEYMAPCHANGED as u32 {
+                    self.keymap_changed(window);
+                }
+                Translated::Ignored
+            }
+
+            Event::Quit { .. }
+            | Event::AppTerminating { .. }
+            | Event::AppLowMemory { .. }
+            | Event::AppWillEnterBackground { .. }
+            | Event::AppDidEnterBackground { .. }
+            | Event::AppWillEnterForeground { .. }
+            | Event::AppDidEnterForeground { .. }
+            | Event::Display { .. }
+            | Event::JoyAxisMotion { .. }
+            | Event::JoyHatMotion { .. }
+            | Event::JoyButtonDown { .. }
+            | Event::JoyButtonUp { .. }
+            | Event::JoyDeviceAdded { .. }
+            | Event::JoyDeviceRemoved { .. }
+            | Event::ControllerAxisMotion { .. }
+            | Event::ControllerButtonDown { .. }
+            | Event::ControllerButtonUp { .. }
+            | Event::ControllerDeviceAdded { .. }
+            | Event::ControllerDeviceRemoved { .. }
+            | Event::ControllerDeviceRemapped { .. }
+            | Event::DollarGesture { .. }
+            | Event::DollarRecord { .. }
+            | Event::MultiGesture { .. }
+            | Event::ClipboardUpdate { .. }
+            | Event::DropText { .. }
+            | Event::DropBegin { .. }
+            | Event::DropComplete { .. }
+            | Event::AudioDeviceAdded { .. }
+            | Event::AudioDeviceRemoved { .. }
+            | Event::User { .. } => Translated::Ignored,
+            #[cfg(feature = "hidapi")]
+            Event::ControllerSensorUpdated { .. } => Translated::Ignored,
+        }
+    }
+
+    // Translates every event in order and merges the responses, see `EventResponse::merge`.
+    // Events for other windows are skipped and do not contribute to the result.
+    #[allow(dead_code)]
+    pub fn on_events<'a>(
+        &mut self,
+        egui_ctx: &Context,
+        window: &Window,
+        events: impl IntoIterator<Item = &'a Event>,
+    ) -> EventResponse {
+        events
+            .into_iter()
+            .fold(EventResponse::default(), |response, event| {
+                response.merge(self.on_event(egui_ctx, window, event))
+            })
+    }
+
+    // Physical pixels per point of the current frame: the UI scale times the drawable scale,
+    // unless overridden for this frame. Rendering must use the same value for the screen
+    // descriptor, or pointer positions and what is drawn disagree after a DPI change.
+    pub fn pixels_per_point(&self) -> f32 {
+        self.frame_pixels_per_point
+            .unwrap_or(self.egui_state.dpi_scaling * self.drawable_scale)
+    }
+
+    // Renders only the next frame at `pixels_per_point`, e.g. to export a high resolution
+    // screenshot, and goes back to the normal scale on the frame after. The screen rect stays
+    // the same size in points, so the layout doesn't change and the frame just comes out with
+    // more pixels; the render target for it has to be sized to match. Input of the overridden
+    // frame is still translated at the normal scale.
+    #[allow(dead_code)]
+    pub fn override_pixels_per_point_once(&mut self, pixels_per_point: f32) {
+        self.next_frame_pixels_per_point = Some(self.config.clamp_pixels_per_point(pixels_per_point));
+    }
+
+    // The configured bounds, and `pixel_perfect`, apply to the scale egui renders at, the UI
+    // scale times the drawable scale. Returns the UI scale that gives the clamped value.
+    fn clamp_ui_scale(&self, ui_scale: f32) -> f32 {
+        self.config.clamp_pixels_per_point(ui_scale * self.drawable_scale) / self.drawable_scale
+    }
+
+    // Converts polled SDL mouse state into egui points with the same transform event
+    // translation uses, e.g. to know where the pointer is before the first motion event.
+    #[allow(dead_code)]
+    pub fn pointer_state(&self, mouse_state: &MouseState) -> (egui::Pos2, Vec<PointerButton>) {
+        let pixels_per_point = self.egui_state.dpi_scaling;
+        let pos = egui::pos2(
+            mouse_state.x() as f32 / pixels_per_point,
+            mouse_state.y() as f32 / pixels_per_point,
+        );
+        let pos = pos - self.custom_screen_rect.map_or(egui::Vec2::ZERO, |rect| rect.min.to_vec2());
+        let buttons = mouse_state
+            .pressed_mouse_buttons()
+            .filter_map(pointer_button)
+            .collect();
+        (pos, buttons)
+    }
+
+    // True once after SDL reported that render targets or the render device were reset
+    // (Direct3D), meaning GPU textures are gone and have to be uploaded again.
+    pub fn take_render_reset(&mut self) -> bool {
+        std::mem::take(&mut self.render_reset)
+    }
+
+    // Takes the initial focus state from the window, instead of assuming it starts focused until
+    // the first focus event says otherwise. egui 0.18's `RawInput` has no `focused` field, so this
+    // only affects the integration's own focus handling (see `pause_time_when_unfocused`).
+    pub fn sync_focus(&mut self, window: &Window) {
+        let focus_flag = sdl2::sys::SDL_WindowFlags::SDL_WINDOW_INPUT_FOCUS as u32;
+        self.focused = window.window_flags() & focus_flag != 0;
+    }
+
+    // egui splits its font atlas and refuses larger images based on this, assuming 2048 pixels
+    // until told otherwise. It only needs to be sent once; egui keeps the last value it was given.
+    pub fn set_max_texture_side(&mut self, max_texture_side: usize) {
+        self.egui_state.raw_input.max_texture_side = Some(max_texture_side);
+    }
+
+    // egui paces its animations by `predicted_dt`, which otherwise assumes 60 Hz.
+    pub fn sync_refresh_rate(&mut self, window: &Window) {
+        if let Ok(mode) = window.display_mode() {
+            if mode.refresh_rate > 0 {
+                let interval = 1.0 / mode.refresh_rate as f32;
+                self.refresh_interval = Some(interval);
+                self.egui_state.raw_input.predicted_dt = interval;
+            }
+        }
+    }
+
+    // Whether the pointer was over any egui window or panel as of the last `end_frame`. Unlike
+    // `Context::wants_pointer_input`, this is also true over parts of a window that don't react to
+    // the pointer, such as labels or empty space, so a click there shouldn't reach a scene behind
+    // the UI either.
+    #[allow(dead_code)]
+    pub fn is_pointer_over_egui_area(&self) -> bool {
+        self.pointer_over_area
+    }
+
+    // egui 0.18 has no Caps Lock `Key`, so apps that bind actions to the key itself (rather than
+    // to the lock state) can poll this instead. SDL sends no text input for Caps Lock, so there
+    // is nothing to filter out of the text stream.
+    #[allow(dead_code)]
+    pub fn is_caps_lock_held(&self) -> bool {
+        self.caps_lock_held
+    }
+
+    // The mouse motion of the frame taken last, summed from SDL's relative deltas, in window
+    // pixels. Unlike differences of egui's pointer position it isn't clamped at the window edge,
+    // so e.g. a middle-drag pan keeps going once the pointer leaves the window mid-drag.
+    #[allow(dead_code)]
+    pub fn relative_motion(&self) -> egui::Vec2 {
+        self.relative_motion
+    }
+
+    // Left and right modifier keys as of the last key event.
+    #[allow(dead_code)]
+    pub fn modifier_sides(&self) -> ModifierSides {
+        self.modifier_sides
+    }
+
+    // False while the window is hidden or minimized; there is no point in painting then.
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    // For layout tests: go through the same paths as real resize events, without SDL. `width`
+    // and `height` are the logical size; the drawable size keeps the current drawable scale.
+    #[allow(dead_code)]
+    pub fn simulate_resize(&mut self, width: u32, height: u32) {
+        let pixels_per_point = self.egui_state.dpi_scaling;
+        let scale = self.drawable_scale;
+        let size_in_pixels = ((width as f32 * scale).round() as u32, (height as f32 * scale).round() as u32);
+        self.update_geometry(size_in_pixels, (width, height), pixels_per_point);
+    }
+
+    #[allow(dead_code)]
+    pub fn simulate_dpi(&mut self, pixels_per_point: f32) {
+        self.set_dpi_scaling(pixels_per_point);
+    }
+
+    // The one place window geometry changes, so the sizes and the scale egui gets always belong
+    // together: the screen rect is the logical size divided by the UI scale, matching how mouse
+    // positions, which SDL reports in logical coordinates, are converted. egui renders at that
+    // scale times the ratio of drawable to logical size, and that product is what gets clamped.
+    pub fn update_geometry(&mut self, size_in_pixels: (u32, u32), logical_size: (u32, u32), pixels_per_point: f32) {
+        self.drawable_size = size_in_pixels;
+        self.window_size = logical_size;
+        self.drawable_scale = if logical_size.0 > 0 && size_in_pixels.0 > 0 {
+            size_in_pixels.0 as f32 / logical_size.0 as f32
+        } else {
+            1.0
+        };
+        self.egui_state.dpi_scaling = self.clamp_ui_scale(pixels_per_point);
+        self.egui_state.raw_input.pixels_per_point = Some(self.pixels_per_point());
+        let (width, height) = logical_size;
+        self.egui_state.update_screen_rect(width, height);
+    }
+
+    // Reads the window's sizes, keeping the current UI scale. A window created without
+    // `SDL_WINDOW_ALLOW_HIGHDPI` is scaled up by the OS on HiDPI displays and renders at its
+    // logical size, so its drawable size is taken to be the logical one; only HiDPI-aware
+    // windows render at the full drawable resolution, which is reflected in `pixels_per_point`.
+    pub fn sync_geometry(&mut self, window: &Window) {
+        let logical_size = window.size();
+        let high_dpi_flag = sdl2::sys::SDL_WindowFlags::SDL_WINDOW_ALLOW_HIGHDPI as u32;
+        let size_in_pixels = if window.window_flags() & high_dpi_flag != 0 {
+            window.drawable_size()
+        } else {
+            logical_size
+        };
+        let pixels_per_point = self.egui_state.dpi_scaling;
+        self.update_geometry(size_in_pixels, logical_size, pixels_per_point);
+    }
+
+    // The drawable size last passed to `update_geometry`, i.e. what the surface should be.
+    #[allow(dead_code)]
+    pub fn drawable_size(&self) -> (u32, u32) {
+        self.drawable_size
+    }
+
+    fn set_dpi_scaling(&mut self, pixels_per_point: f32) {
+        self.update_geometry(self.drawable_size, self.window_size, pixels_per_point);
+    }
+
+    // Motion events carry the buttons SDL considers held. If a press or release never reached
+    // us, report the missing one at the last position so egui's drag state heals itself.
+    fn reconcile_held_buttons(&mut self, mousestate: &MouseState) {
+        let sdl_buttons = [
+            (PointerButton::Primary, mousestate.left()),
+            (PointerButton::Secondary, mousestate.right()),
+            (PointerButton::Middle, mousestate.middle()),
+        ];
+        for (button, sdl_held) in sdl_buttons {
+            if self.held_buttons.contains(&button) == sdl_held {
+                continue;
+            }
+            if sdl_held {
+                self.held_buttons.push(button);
+            } else {
+                self.held_buttons.retain(|held| *held != button);
+            }
+            self.egui_state.raw_input.events.push(egui::Event::PointerButton {
+                pos: self.egui_state.mouse_pointer_position,
+                button,
+                pressed: sdl_held,
+                modifiers: self.egui_state.modifiers,
+            });
+        }
+    }
+
+    // Keys released after focus moved elsewhere never reach us either; without a release egui
+    // would consider them held until they are pressed again.
+    fn release_held_keys(&mut self) {
+        let modifiers = self.egui_state.modifiers;
+        for key in self.held_keys.drain() {
+            self.egui_state.raw_input.events.push(egui::Event::Key {
+                key,
+                pressed: false,
+                modifiers,
+            });
+        }
+    }
+
+    // The egui keys and pointer buttons pressed and not yet released, as tracked from SDL's
+    // events, e.g. to check for stuck input. Both are cleared when the window loses focus.
+    #[allow(dead_code)]
+    pub fn held_keys(&self) -> &HashSet<Key> {
+        &self.held_keys
+    }
+
+    #[allow(dead_code)]
+    pub fn held_buttons(&self) -> &[PointerButton] {
+        &self.held_buttons
+    }
+
+    // When focus is lost mid-drag (e.g. the button is released over another window) SDL never
+    // reports the release, and egui would keep dragging. Finish the drag at the last position
+    // clamped to the window so widgets end up in a sane state.
+    fn release_held_buttons(&mut self) {
+        if self.held_buttons.is_empty() {
+            return;
+        }
+        let pos = self.screen_rect().clamp(self.egui_state.mouse_pointer_position);
+        let modifiers = self.egui_state.modifiers;
+        let events = &mut self.egui_state.raw_input.events;
+        events.push(egui::Event::PointerMoved(pos));
+        for button in self.held_buttons.drain(..) {
+            events.push(egui::Event::PointerButton {
+                pos,
+                button,
+                pressed: false,
+                modifiers,
+            });
+        }
+    }
+
+    // `raw_input.screen_rect` is only set on frames where it changed, this is always current.
+    // SDL window coordinates to egui points, clamped to the window.
+    fn pointer_position(&self, x: i32, y: i32) -> egui::Pos2 {
+        let pixels_per_point = self.egui_state.dpi_scaling;
+        let pos = egui::pos2(x as f32 / pixels_per_point, y as f32 / pixels_per_point);
+        self.screen_rect().clamp(pos)
+    }
+
+    fn screen_rect(&self) -> egui::Rect {
+        let (width, height) = self.window_size;
+        let size = egui::vec2(width as f32, height as f32) / self.egui_state.dpi_scaling;
+        egui::Rect::from_min_size(egui::Pos2::ZERO, size)
+    }
+
+    fn translate_touch(&mut self, event: &Event) -> bool {
+        let offset = self.custom_screen_rect.map_or(egui::Vec2::ZERO, |rect| rect.min.to_vec2());
+        let window_rect = self.screen_rect().translate(-offset);
+        let modifiers = self.egui_state.modifiers;
+        let coalesce = self.config.coalesce_touch_motion;
+        let tap_threshold = self.config.touch_tap_threshold / self.egui_state.dpi_scaling;
+        let events = &mut self.egui_state.raw_input.events;
+        self.touch.on_event(event, window_rect, modifiers, coalesce, tap_threshold, events)
+    }
+
+    // sdl2 0.35 has no variant for SDL_KEYMAPCHANGED, it arrives as `Event::Unknown`. Keycodes are
+    // resolved by SDL per event and text arrives as `TextInput`, which already follows the active
+    // layout, so the only cached state to refresh is the modifiers.
+    fn keymap_changed(&mut self, window: &Window) {
+        let mod_state = window.subsystem().sdl().keyboard().mod_state();
+        self.update_modifiers(mod_state);
+    }
+
+    fn update_modifiers(&mut self, keymod: Mod) {
+        let modifiers = modifiers_from_sdl(keymod);
+        self.egui_state.modifiers = modifiers;
+        self.egui_state.raw_input.modifiers = modifiers;
+        self.modifier_sides = ModifierSides::from_sdl(keymod);
+    }
+
+    fn translate_key(&mut self, window: &Window, keycode: Option<Keycode>, keymod: Mod, pressed: bool) {
+        // Modifiers are refreshed for every key, including the modifier keys themselves, which
+        // have no egui `Key`.
+        self.update_modifiers(keymod);
+        let keycode = match keycode {
+            Some(keycode) => keycode,
+            None => return,
+        };
+        if keycode == Keycode::CapsLock {
+            self.caps_lock_held = pressed;
+        }
+        if let Some(key) = keys::translate_key(keycode) {
+            if pressed {
+                self.held_keys.insert(key);
+            } else {
+                self.held_keys.remove(&key);
+            }
+            let modifiers = self.egui_state.modifiers;
+            let typing = self.text_field_focused && !modifiers.command && !modifiers.ctrl && !modifiers.alt;
+            if self.config.suppress_printable_keys_while_typing && typing && keys::is_printable(key) {
+                return;
+            }
+            self.egui_state.raw_input.events.push(egui::Event::Key {
+                key,
+                pressed,
+                modifiers,
+            });
+        }
+        if pressed {
+            self.translate_clipboard_key(window, keycode);
+            self.translate_menu_key(keycode);
+        }
+    }
+
+    fn translate_menu_key(&mut self, keycode: Keycode) {
+        if !self.config.menu_key_opens_context_menu || !matches!(keycode, Keycode::Application | Keycode::Menu) {
+            return;
+        }
+        let pos = self.egui_state.mouse_pointer_position;
+        let modifiers = self.egui_state.modifiers;
+        for pressed in [true, false] {
+            self.egui_state.raw_input.events.push(egui::Event::PointerButton {
+                pos,
+                button: PointerButton::Secondary,
+                pressed,
+                modifiers,
+            });
+        }
+    }
+
+    // Cmd/Ctrl+C/X/V, and the dedicated Copy/Cut/Paste keys found on some keyboards.
+    fn translate_clipboard_key(&mut self, window: &Window, keycode: Keycode) {
+        let command = self.egui_state.modifiers.command;
+        let event = match keycode {
+            Keycode::Copy => egui::Event::Copy,
+            Keycode::C if command => egui::Event::Copy,
+            Keycode::Cut => egui::Event::Cut,
+            Keycode::X if command => egui::Event::Cut,
+            Keycode::Paste | Keycode::V if command || keycode == Keycode::Paste => {
+                match window.subsystem().clipboard().clipboard_text() {
+                    Ok(contents) => egui::Event::Paste(contents),
+                    Err(_) => return,
+                }
+            }
+            _ => return,
+        };
+        self.egui_state.raw_input.events.push(event);
+    }
+
+    fn translate_wheel(&mut self, window: &Window, x: i32, y: i32) {
+        let mut points = self.config.points_per_wheel_notch;
+        if self.config.scroll_in_pixels {
+            points /= self.pixels_per_point();
+        }
+        let delta = egui::vec2(x as f32 * points, y as f32 * points);
+        let mod_state = window.subsystem().sdl().keyboard().mod_state();
+        let events = &mut self.egui_state.raw_input.events;
+        let ctrl = mod_state.intersects(Mod::LCTRLMOD | Mod::RCTRLMOD);
+        let alt = !ctrl && mod_state.intersects(Mod::LALTMOD | Mod::RALTMOD);
+        if alt && self.config.alt_scroll == AltScroll::Suppress {
+            return;
+        }
+        if ctrl || (alt && self.config.alt_scroll == AltScroll::Zoom) {
+            let mut step = delta.y / 125.0;
+            if mod_state.intersects(self.config.fine_zoom_modifiers) {
+                step *= self.config.fine_zoom_scale;
+            }
+            events.push(egui::Event::Zoom(step.exp()));
+            return;
+        }
+        let scroll = if mod_state.intersects(Mod::LSHIFTMOD | Mod::RSHIFTMOD) {
+            egui::vec2(delta.x + delta.y, 0.0)
+        } else {
+            delta
+        };
+        if self.config.coalesce_scroll {
+            if let Some(egui::Event::Scroll(last)) = events.last_mut() {
+                *last += scroll;
+                return;
+            }
+        }
+        events.push(egui::Event::Scroll(scroll));
+    }
+
+    pub fn update_time(&mut self, running_time: Option<f64>, delta: f32) {
+        let time = running_time.map(|time| self.pausable_time(time));
+        self.egui_state.update_time(time, delta);
+        if let Some(interval) = self.refresh_interval {
+            self.egui_state.raw_input.predicted_dt = interval;
+        }
+    }
+
+    // The time egui sees: the value set by `update_time` for the coming frame, or the one taken
+    // last once `take_raw_input` has moved it out. Includes the pause applied while unfocused.
+    #[allow(dead_code)]
+    pub fn egui_time(&self) -> Option<f64> {
+        self.egui_state.raw_input.time.or(self.taken_time)
+    }
+
+    fn pausable_time(&mut self, time: f64) -> f64 {
+        if let Some(last) = self.last_time {
+            if self.config.pause_time_when_unfocused && !self.focused {
+                self.paused_time += time - last;
+            }
+        }
+        self.last_time = Some(time);
+        time - self.paused_time
+    }
+
+    // Alternative to `update_time` that takes the time from SDL's millisecond tick counter, so the
+    // caller doesn't have to keep a running time in seconds. The delta is measured between calls.
+    #[allow(dead_code)]
+    pub fn update_time_from_sdl(&mut self, timer: &TimerSubsystem) {
+        let ticks = timer.ticks();
+        let delta = match self.last_sdl_ticks {
+            Some(last) => ticks.wrapping_sub(last) as f32 / 1000.0,
+            None => self.egui_state.raw_input.predicted_dt,
+        };
+        self.last_sdl_ticks = Some(ticks);
+        self.update_time(Some(ticks as f64 / 1000.0), delta);
+    }
+
+    pub fn take_raw_input(&mut self) -> RawInput {
+        let mut raw_input = self.egui_state.raw_input.take();
+        self.taken_time = raw_input.time;
+        self.relative_motion = std::mem::take(&mut self.pending_relative_motion);
+        let overridden = self.frame_pixels_per_point.is_some();
+        self.frame_pixels_per_point = self.next_frame_pixels_per_point.take();
+        if self.frame_pixels_per_point.is_some() || overridden {
+            raw_input.pixels_per_point = Some(self.pixels_per_point());
+        }
+        if let Some(rect) = self.custom_screen_rect {
+            raw_input.screen_rect = Some(egui::Rect::from_min_size(egui::Pos2::ZERO, rect.size()));
+        }
+        raw_input
+    }
+
+    // Feeds a previously recorded frame of input into the next `take_raw_input`. Its events are
+    // appended after whatever live input was translated this frame, so to replay deterministically
+    // stop translating SDL input events while replaying. The recorded time, screen rect, scale and
+    // modifiers replace the live ones for that frame only; the next live event or `update_time`
+    // sets them again.
+    #[allow(dead_code)]
+    pub fn replay_raw_input(&mut self, recorded: RawInput) {
+        let raw_input = &mut self.egui_state.raw_input;
+        raw_input.events.extend(recorded.events);
+        raw_input.dropped_files.extend(recorded.dropped_files);
+        raw_input.modifiers = recorded.modifiers;
+        if recorded.time.is_some() {
+            raw_input.time = recorded.time;
+        }
+        if recorded.screen_rect.is_some() {
+            raw_input.screen_rect = recorded.screen_rect;
+        }
+        if recorded.pixels_per_point.is_some() {
+            raw_input.pixels_per_point = recorded.pixels_per_point;
+        }
+        raw_input.predicted_dt = recorded.predicted_dt;
+    }
+
+    // `begin_frame` and `end_frame` bracket a `Context::run`: the first updates the time and takes
+    // the input translated since the last frame, the second applies the platform output.
+    pub fn begin_frame(&mut self, running_time: Option<f64>, delta: f32) -> RawInput {
+        self.update_time(running_time, delta);
+        self.take_raw_input()
+    }
+
+    pub fn end_frame(&mut self, egui_ctx: &Context, window: &Window, full_output: &FullOutput) {
+        self.pointer_over_area = egui_ctx.is_pointer_over_area();
+        self.process_output(window, &full_output.platform_output);
+    }
+
+    // One whole frame for integration tests: translates `events`, runs egui on them and applies
+    // the platform output (clipboard, cursor, text input) like the main loop does, returning
+    // egui's output together with what was applied to SDL. SDL still needs a window to translate
+    // against, but none has to be visible: with `SDL_VIDEODRIVER=dummy` SDL creates windows
+    // without a display, and no GPU is involved. Time is left to egui's `predicted_dt`.
+    #[allow(dead_code)]
+    #[cfg(feature = "test-harness")]
+    pub fn run_frame(
+        &mut self,
+        egui_ctx: &Context,
+        window: &Window,
+        events: &[Event],
+        run_ui: impl FnOnce(&Context),
+    ) -> (FullOutput, AppliedOutput) {
+        self.on_events(egui_ctx, window, events);
+        let output = egui_ctx.run(self.take_raw_input(), run_ui);
+        self.end_frame(egui_ctx, window, &output);
+        let platform_output = &output.platform_output;
+        let applied = AppliedOutput {
+            clipboard_text: Some(platform_output.copied_text.clone()).filter(|text| !text.is_empty()),
+            system_cursor: self.egui_state.fused_cursor.icon,
+            text_input_rect: self.text_input_rect,
+        };
+        (output, applied)
+    }
+
+    // Runs egui right away on the input translated so far and applies its platform output,
+    // merging the rest into `pending`. Instead of buffering a whole display frame of input,
+    // an app can call this after each batch of events it polls and paint (taking `pending`)
+    // only once per display frame, so egui reacts to input up to a frame sooner. The UI code
+    // runs more often in exchange; build with the `input-latency` feature to measure the gain.
+    // Like `begin_frame` it sets the time, so several runs within one display frame don't each
+    // advance egui's clock by `predicted_dt`; pass the time of each call rather than the time
+    // the display frame started.
+    pub fn process_and_run(
+        &mut self,
+        egui_ctx: &Context,
+        window: &Window,
+        running_time: Option<f64>,
+        delta: f32,
+        pending: &mut Option<FullOutput>,
+        run_ui: impl FnOnce(&Context),
+    ) {
+        let output = egui_ctx.run(self.begin_frame(running_time, delta), run_ui);
+        self.end_frame(egui_ctx, window, &output);
+        match pending {
+            // `FullOutput::append` keeps only the last run's `needs_repaint`, but the shapes of an
+            // earlier run that wanted one haven't been painted yet either.
+            Some(pending) => {
+                let needs_repaint = pending.needs_repaint || output.needs_repaint;
+                pending.append(output);
+                pending.needs_repaint = needs_repaint;
+            }
+            None => *pending = Some(output),
+        }
+    }
+
+    // Call with the output of the `Context::run` that just finished, before translating the next
+    // batch of events. Text input and the IME rect are decided from this output rather than from
+    // `wants_keyboard_input` of the previous frame, so clicking a text field enables them on
+    // the same frame focus moves instead of one frame later.
+    pub fn process_output(&mut self, window: &Window, egui_output: &PlatformOutput) {
+        if !egui_output.copied_text.is_empty() {
+            if let Err(e) = window.subsystem().clipboard().set_clipboard_text(&egui_output.copied_text) {
+                eprintln!("Unable to set clipboard content to SDL clipboard: {}", e);
+            }
+            self.copied_text = Some(egui_output.copied_text.clone());
+        }
+        cursor::set_cursor(&mut self.egui_state.fused_cursor, egui_output.cursor_icon, Cursor::from_system);
+        let text_field_focused = egui_output.text_cursor_pos.is_some();
+        if self.text_field_focused && !text_field_focused {
+            self.cancel_composition(window);
+        }
+        self.text_field_focused = text_field_focused;
+        self.update_screen_keyboard(window, egui_output);
+        self.update_text_input_rect(window, egui_output);
+    }
+
+    // Makes sure the last text egui copied is what the system clipboard holds, setting it again
+    // if not. Call it before quitting. SDL sets the clipboard synchronously, but on X11 and Wayland
+    // the text is served by the app itself and is gone once it exits unless a clipboard manager
+    // took a copy; nothing SDL 2.0 offers can hand it over explicitly.
+    pub fn flush_clipboard(&self, window: &Window) -> Result<(), String> {
+        let copied_text = match &self.copied_text {
+            Some(text) => text,
+            None => return Ok(()),
+        };
+        let clipboard = window.subsystem().clipboard();
+        if clipboard.clipboard_text().as_ref() != Ok(copied_text) {
+            clipboard.set_clipboard_text(copied_text)?;
+        }
+        Ok(())
+    }
+
+    // Follow the caret of the focused text field with SDL's text input rect. Besides placing the
+    // IME candidate window, screen magnifiers use it to track the caret, so it is kept up to date
+    // whenever a field is focused rather than only while composing.
+    fn update_text_input_rect(&mut self, window: &Window, egui_output: &PlatformOutput) {
+        let pos = match egui_output.text_cursor_pos {
+            Some(pos) => pos,
+            None => {
+                self.text_input_rect = None;
+                return;
+            }
+        };
+        // Back from egui's space into the window's when egui only covers part of it.
+        let pos = pos + self.custom_screen_rect.map_or(egui::Vec2::ZERO, |rect| rect.min.to_vec2());
+        let pixels_per_point = self.egui_state.dpi_scaling;
+        let rect = sdl2::rect::Rect::new(
+            (pos.x * pixels_per_point) as i32,
+            (pos.y * pixels_per_point) as i32,
+            1,
+            1,
+        );
+        if self.text_input_rect != Some(rect) {
+            window.subsystem().text_input().set_rect(rect);
+            self.text_input_rect = Some(rect);
+        }
+    }
+
+    // Moves the mouse to the middle of the window, e.g. when a game menu opens. SDL reports the
+    // warp as ordinary motion; that event is filtered out so egui sees no pointer movement, and
+    // the pointer position egui knows only changes with the next real motion. Only the very next
+    // motion is checked: if SDL reports the warp elsewhere or not at all, a later real move back
+    // to the center isn't swallowed.
+    #[allow(dead_code)]
+    pub fn center_cursor(&mut self, window: &Window) {
+        let (width, height) = window.size();
+        let (x, y) = ((width / 2) as i32, (height / 2) as i32);
+        window.subsystem().sdl().mouse().warp_mouse_in_window(window, x, y);
+        self.warp_target = Some((x, y));
+    }
+
+    // Asks SDL rather than tracking our own calls, so it also reflects text input the app started
+    // or stopped itself. Without it no `TextInput` events arrive and typing into egui does nothing.
+    #[allow(dead_code)]
+    pub fn is_text_input_active(&self, window: &Window) -> bool {
+        window.subsystem().text_input().is_active()
+    }
+
+    // The composition text only ever lived in the IME's own window, since egui 0.18 isn't sent
+    // composition events, so there is nothing to retract in egui. Stopping text input makes the
+    // IME drop it instead of committing it into whatever gets focus next. On desktop text input is
+    // switched straight back on; with an on-screen keyboard `update_screen_keyboard` leaves it off.
+    fn cancel_composition(&mut self, window: &Window) {
+        if !self.composing {
+            return;
+        }
+        self.composing = false;
+        let text_input = window.subsystem().text_input();
+        text_input.stop();
+        if !text_input.has_screen_keyboard_support() {
+            text_input.start();
+        } else {
+            self.screen_keyboard_requested = false;
+        }
+    }
+
+    // On desktop SDL keeps text input enabled from startup, so `TextInput` events arrive without
+    // any action from us. On platforms with an on-screen keyboard (Android, iOS) starting text
+    // input is what shows the keyboard, so it is toggled to follow egui's text focus: egui
+    // reports `text_cursor_pos` only while a text field has keyboard focus.
+    fn update_screen_keyboard(&mut self, window: &Window, egui_output: &PlatformOutput) {
+        let text_input = window.subsystem().text_input();
+        if !text_input.has_screen_keyboard_support() {
+            return;
+        }
+        let wants_keyboard = egui_output.text_cursor_pos.is_some();
+        if wants_keyboard == self.screen_keyboard_requested {
+            return;
+        }
+        if wants_keyboard {
+            text_input.start();
+        } else {
+            text_input.stop();
+        }
+        self.screen_keyboard_requested = wants_keyboard;
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::touch::tests::finger;
+    use egui::TouchPhase;
+    use std::sync::Mutex;
+
+    // SDL can only be initialized once at a time, so tests that need a window take turns, the
+    // cursor tests included.
+    static SDL_LOCK: Mutex<()> = Mutex::new(());
+
+    pub(crate) fn with_window(test: impl FnOnce(&Window)) {
+        let _guard = SDL_LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        std::env::set_var("SDL_VIDEODRIVER", "dummy");
+        let sdl = sdl2::init().unwrap();
+        let video = sdl.video().unwrap();
+        let window = video.window("test", 800, 600).build().unwrap();
+        test(&window);
+    }
+
+    fn button_down(window: &Window, x: i32, y: i32) -> Event {
+        Event::MouseButtonDown {
+            timestamp: 0,
+            window_id: window.id(),
+            which: 0,
+            mouse_btn: MouseButton::Left,
+            clicks: 1,
+            x,
+            y,
+        }
+    }
+
+    fn window_event(window: &Window, win_event: WindowEvent) -> Event {
+        Event::Window {
+            timestamp: 0,
+            window_id: window.id(),
+            win_event,
+        }
+    }
+
+    fn motion(window: &Window, which: u32, buttons: u32, x: i32, y: i32) -> Event {
+        Event::MouseMotion {
+            timestamp: 0,
+            window_id: window.id(),
+            which,
+            mousestate: MouseState::from_sdl_state(buttons),
+            x,
+            y,
+            xrel: 0,
+            yrel: 0,
+        }
+    }
+
+    fn key(window: &Window, keycode: Keycode, keymod: Mod, pressed: bool) -> Event {
+        let (timestamp, window_id, keycode, scancode, repeat) = (0, window.id(), Some(keycode), None, false);
+        if pressed {
+            Event::KeyDown { timestamp, window_id, keycode, scancode, keymod, repeat }
+        } else {
+            Event::KeyUp { timestamp, window_id, keycode, scancode, keymod, repeat }
+        }
+    }
+
+    fn key_events(events: &[egui::Event]) -> Vec<(Key, bool, Modifiers)> {
+        events
+            .iter()
+            .filter_map(|event| match event {
+                egui::Event::Key { key, pressed, modifiers } => Some((*key, *pressed, *modifiers)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn touch_positions(events: &[egui::Event]) -> Vec<egui::Pos2> {
+        events
+            .iter()
+            .filter_map(|event| match event {
+                egui::Event::Touch { pos, .. } => Some(*pos),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn click_inside_custom_screen_rect_is_relative_to_it() {
+        with_window(|window| {
+            let ctx = Context::default();
+            let mut state = InputState::new(800, 600, 1.0);
+            state.set_screen_rect(egui::Rect::from_min_size(egui::pos2(100.0, 50.0), egui::vec2(200.0, 100.0)));
+            state.on_event(&ctx, window, &button_down(window, 150, 80));
+            let raw_input = state.take_raw_input();
+            assert!(raw_input.events.iter().any(|event| matches!(
+                event,
+                egui::Event::PointerButton { pos, pressed: true, .. } if *pos == egui::pos2(50.0, 30.0)
+            )));
+            assert_eq!(raw_input.screen_rect, Some(egui::Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(200.0, 100.0))));
+        });
+    }
+
+    #[test]
+    fn coalesced_touch_moves_are_offset_by_custom_screen_rect() {
+        with_window(|window| {
+            let ctx = Context::default();
+            let config = InputConfig {
+                touch_input: true,
+                coalesce_touch_motion: true,
+                ..Default::default()
+            };
+            let mut state = InputState::with_config(800, 600, 1.0, config);
+            state.set_screen_rect(egui::Rect::from_min_size(egui::pos2(100.0, 50.0), egui::vec2(200.0, 100.0)));
+            state.on_event(&ctx, window, &finger(TouchPhase::Start, 7, 0.25, 0.25));
+            state.on_event(&ctx, window, &finger(TouchPhase::Move, 7, 0.25, 0.5));
+            state.on_event(&ctx, window, &finger(TouchPhase::Move, 7, 0.5, 0.5));
+            let raw_input = state.take_raw_input();
+            assert_eq!(
+                touch_positions(&raw_input.events),
+                vec![egui::pos2(100.0, 100.0), egui::pos2(300.0, 250.0)]
+            );
+            assert!(raw_input.events.contains(&egui::Event::PointerMoved(egui::pos2(300.0, 250.0))));
+        });
+    }
+
+    #[test]
+    fn rendered_scale_is_clamped_after_drawable_scale() {
+        let mut state = InputState::new(800, 600, 1.0);
+        state.update_geometry((1600, 1200), (800, 600), 4.0);
+        assert_eq!(state.pixels_per_point(), 5.0);
+        assert_eq!(state.take_raw_input().pixels_per_point, Some(5.0));
+    }
+
+    #[test]
+    fn pixel_perfect_renders_one_pixel_per_point_on_hidpi() {
+        let config = InputConfig {
+            pixel_perfect: true,
+            ..Default::default()
+        };
+        let mut state = InputState::with_config(800, 600, 2.0, config);
+        state.update_geometry((1600, 1200), (800, 600), 2.0);
+        assert_eq!(state.pixels_per_point(), 1.0);
+        assert_eq!(state.take_raw_input().pixels_per_point, Some(1.0));
+    }
+
+    #[test]
+    fn scroll_in_pixels_divides_by_rendered_scale() {
+        with_window(|window| {
+            let ctx = Context::default();
+            let config = InputConfig {
+                scroll_in_pixels: true,
+                scroll_policy: ScrollPolicy::Always,
+                ..Default::default()
+            };
+            let mut state = InputState::with_config(800, 600, 1.0, config);
+            let wheel = Event::MouseWheel {
+                timestamp: 0,
+                window_id: window.id(),
+                which: 0,
+                x: 0,
+                y: 1,
+                direction: MouseWheelDirection::Normal,
+            };
+            state.on_event(&ctx, window, &wheel);
+            assert_eq!(state.take_raw_input().events, vec![egui::Event::Scroll(egui::vec2(0.0, 8.0))]);
+
+            state.update_geometry((1600, 1200), (800, 600), 1.0);
+            state.on_event(&ctx, window, &wheel);
+            assert_eq!(state.take_raw_input().events, vec![egui::Event::Scroll(egui::vec2(0.0, 4.0))]);
+
+            state.update_geometry((1600, 1200), (800, 600), 2.0);
+            state.on_event(&ctx, window, &wheel);
+            assert_eq!(state.take_raw_input().events, vec![egui::Event::Scroll(egui::vec2(0.0, 2.0))]);
+        });
+    }
+
+    #[test]
+    fn pixels_per_point_override_keeps_the_screen_rect() {
+        let mut state = InputState::new(800, 600, 1.0);
+        state.take_raw_input();
+        state.override_pixels_per_point_once(3.0);
+        let raw_input = state.take_raw_input();
+        assert_eq!(raw_input.pixels_per_point, Some(3.0));
+        assert_eq!(raw_input.screen_rect, None);
+        assert_eq!(state.screen_rect().size(), egui::vec2(800.0, 600.0));
+        assert_eq!(state.take_raw_input().pixels_per_point, Some(1.0));
+    }
+
+    #[test]
+    fn releasing_ctrl_clears_the_modifier() {
+        with_window(|window| {
+            let ctx = Context::default();
+            let mut state = InputState::new(800, 600, 1.0);
+            state.on_event(&ctx, window, &key(window, Keycode::LCtrl, Mod::LCTRLMOD, true));
+            assert!(state.egui_state.raw_input.modifiers.ctrl);
+            state.on_event(&ctx, window, &key(window, Keycode::A, Mod::LCTRLMOD, true));
+            state.on_event(&ctx, window, &key(window, Keycode::A, Mod::LCTRLMOD, false));
+            state.on_event(&ctx, window, &key(window, Keycode::LCtrl, Mod::empty(), false));
+            assert!(!state.egui_state.raw_input.modifiers.ctrl);
+            state.on_event(&ctx, window, &key(window, Keycode::A, Mod::empty(), true));
+            let modifiers: Vec<bool> = key_events(&state.take_raw_input().events)
+                .into_iter()
+                .map(|(_, _, modifiers)| modifiers.ctrl)
+                .collect();
+            assert_eq!(modifiers, vec![true, true, false]);
+        });
+    }
+
+    #[test]
+    fn pixels_per_point_is_clamped_to_the_defaults() {
+        let mut state = InputState::new(800, 600, 10.0);
+        assert_eq!(state.pixels_per_point(), 5.0);
+        state.simulate_dpi(0.1);
+        assert_eq!(state.pixels_per_point(), 0.25);
+        assert_eq!(state.take_raw_input().pixels_per_point, Some(0.25));
+        state.override_pixels_per_point_once(20.0);
+        state.take_raw_input();
+        assert_eq!(state.pixels_per_point(), 5.0);
+    }
+
+    #[test]
+    fn pixels_per_point_is_clamped_to_configured_bounds() {
+        let config = InputConfig {
+            min_pixels_per_point: 1.0,
+            max_pixels_per_point: 2.0,
+            ..Default::default()
+        };
+        let mut state = InputState::with_config(800, 600, 0.5, config);
+        assert_eq!(state.pixels_per_point(), 1.0);
+        state.simulate_dpi(1.5);
+        assert_eq!(state.pixels_per_point(), 1.5);
+        state.simulate_dpi(3.0);
+        assert_eq!(state.pixels_per_point(), 2.0);
+    }
+
+    #[test]
+    fn touch_synthesized_motion_is_dropped_with_touch_input() {
+        with_window(|window| {
+            let ctx = Context::default();
+            let config = InputConfig {
+                touch_input: true,
+                ..Default::default()
+            };
+            let mut state = InputState::with_config(800, 600, 1.0, config);
+            let response = state.on_event(&ctx, window, &motion(window, SDL_TOUCH_MOUSEID, 0, 10, 10));
+            assert_eq!(response, EventResponse::default());
+            assert!(state.take_raw_input().events.is_empty());
+
+            let mut state = InputState::new(800, 600, 1.0);
+            state.on_event(&ctx, window, &motion(window, SDL_TOUCH_MOUSEID, 0, 10, 10));
+            assert!(!state.take_raw_input().events.is_empty());
+        });
+    }
+
+    #[test]
+    fn drag_released_outside_finishes_at_clamped_position() {
+        with_window(|window| {
+            let ctx = Context::default();
+            let mut state = InputState::new(800, 600, 1.0);
+            state.on_event(&ctx, window, &button_down(window, 100, 100));
+            state.on_event(&ctx, window, &motion(window, 0, 1, 900, 300));
+            state.take_raw_input();
+            state.on_event(&ctx, window, &window_event(window, WindowEvent::FocusLost));
+            let edge = egui::pos2(800.0, 300.0);
+            let released = egui::Event::PointerButton {
+                pos: edge,
+                button: PointerButton::Primary,
+                pressed: false,
+                modifiers: Modifiers::default(),
+            };
+            assert_eq!(state.take_raw_input().events, vec![egui::Event::PointerMoved(edge), released]);
+            assert!(state.held_buttons().is_empty());
+        });
+    }
+
+    #[test]
+    fn drag_past_the_edge_keeps_moving_clamped() {
+        with_window(|window| {
+            let ctx = Context::default();
+            let mut state = InputState::new(800, 600, 1.0);
+            state.on_event(&ctx, window, &button_down(window, 780, 100));
+            state.take_raw_input();
+            state.on_event(&ctx, window, &motion(window, 0, 1, 850, 120));
+            state.on_event(&ctx, window, &motion(window, 0, 1, -20, 650));
+            assert_eq!(
+                state.take_raw_input().events,
+                vec![
+                    egui::Event::PointerMoved(egui::pos2(800.0, 120.0)),
+                    egui::Event::PointerMoved(egui::pos2(0.0, 600.0)),
+                ]
+            );
+        });
+    }
+
+    #[test]
+    fn printable_keys_are_suppressed_while_typing() {
+        with_window(|window| {
+            let ctx = Context::default();
+            let config = InputConfig {
+                suppress_printable_keys_while_typing: true,
+                ..Default::default()
+            };
+            let mut state = InputState::with_config(800, 600, 1.0, config);
+            let typing = PlatformOutput {
+                text_cursor_pos: Some(egui::pos2(10.0, 10.0)),
+                ..Default::default()
+            };
+            state.process_output(window, &typing);
+            state.on_event(&ctx, window, &key(window, Keycode::A, Mod::empty(), true));
+            state.on_event(&ctx, window, &key(window, Keycode::Left, Mod::empty(), true));
+            state.on_event(&ctx, window, &key(window, Keycode::A, Mod::LCTRLMOD, true));
+            let keys: Vec<(Key, bool)> = key_events(&state.take_raw_input().events)
+                .into_iter()
+                .map(|(key, _, modifiers)| (key, modifiers.ctrl))
+                .collect();
+            assert_eq!(keys, vec![(Key::ArrowLeft, false), (Key::A, true)]);
+
+            state.process_output(window, &PlatformOutput::default());
+            state.on_event(&ctx, window, &key(window, Keycode::A, Mod::empty(), true));
+            assert_eq!(key_events(&state.take_raw_input().events).len(), 1);
+        });
+    }
+
+    #[test]
+    fn motion_button_mask_heals_missed_button_events() {
+        with_window(|window| {
+            let ctx = Context::default();
+            let mut state = InputState::new(800, 600, 1.0);
+            state.on_event(&ctx, window, &motion(window, 0, 0, 10, 10));
+            state.take_raw_input();
+            let primary = |pos, pressed| egui::Event::PointerButton {
+                pos,
+                button: PointerButton::Primary,
+                pressed,
+                modifiers: Modifiers::default(),
+            };
+
+            // The press never arrived, but the next motion reports the left button held.
+            state.on_event(&ctx, window, &motion(window, 0, 1, 20, 20));
+            assert_eq!(
+                state.take_raw_input().events,
+                vec![primary(egui::pos2(10.0, 10.0), true), egui::Event::PointerMoved(egui::pos2(20.0, 20.0))]
+            );
+            assert_eq!(state.held_buttons(), &[PointerButton::Primary]);
+
+            // Neither did the release.
+            state.on_event(&ctx, window, &motion(window, 0, 0, 30, 30));
+            assert_eq!(
+                state.take_raw_input().events,
+                vec![primary(egui::pos2(20.0, 20.0), false), egui::Event::PointerMoved(egui::pos2(30.0, 30.0))]
+            );
+            assert!(state.held_buttons().is_empty());
+        });
+    }
+
+    #[test]
+    fn dropped_files_reach_egui_for_one_frame() {
+        with_window(|window| {
+            let ctx = Context::default();
+            let mut state = InputState::new(800, 600, 1.0);
+            let drop = Event::DropFile {
+                timestamp: 0,
+                window_id: window.id(),
+                filename: "/tmp/dropped.txt".to_string(),
+            };
+            state.on_event(&ctx, window, &drop);
+            let dropped = state.take_raw_input().dropped_files;
+            assert_eq!(dropped.len(), 1);
+            assert_eq!(dropped[0].name, "/tmp/dropped.txt");
+            assert!(state.take_raw_input().dropped_files.is_empty());
+        });
+    }
+
+    #[test]
+    fn menu_key_right_clicks_at_the_pointer() {
+        with_window(|window| {
+            let ctx = Context::default();
+            let config = InputConfig {
+                menu_key_opens_context_menu: true,
+                ..Default::default()
+            };
+            let mut state = InputState::with_config(800, 600, 1.0, config);
+            state.on_event(&ctx, window, &motion(window, 0, 0, 40, 50));
+            state.take_raw_input();
+            state.on_event(&ctx, window, &key(window, Keycode::Application, Mod::empty(), true));
+            let secondary = |pressed| egui::Event::PointerButton {
+                pos: egui::pos2(40.0, 50.0),
+                button: PointerButton::Secondary,
+                pressed,
+                modifiers: Modifiers::default(),
+            };
+            assert_eq!(state.take_raw_input().events, vec![secondary(true), secondary(false)]);
+        });
+    }
+
+    #[test]
+    fn touch_synthesized_buttons_are_dropped_with_touch_input() {
+        with_window(|window| {
+            let ctx = Context::default();
+            let config = InputConfig {
+                touch_input: true,
+                ..Default::default()
+            };
+            let mut state = InputState::with_config(800, 600, 1.0, config);
+            let (timestamp, window_id, which, mouse_btn, x, y) = (0, window.id(), SDL_TOUCH_MOUSEID, MouseButton::Left, 10, 10);
+            let down = Event::MouseButtonDown { timestamp, window_id, which, mouse_btn, clicks: 1, x, y };
+            let up = Event::MouseButtonUp { timestamp, window_id, which, mouse_btn, clicks: 1, x, y };
+            assert_eq!(state.on_event(&ctx, window, &down), EventResponse::default());
+            assert_eq!(state.on_event(&ctx, window, &up), EventResponse::default());
+            assert!(state.take_raw_input().events.is_empty());
+            assert!(state.held_buttons().is_empty());
+        });
+    }
+
+    #[test]
+    fn repeated_focus_events_take_effect_once() {
+        with_window(|window| {
+            let ctx = Context::default();
+            let mut state = InputState::new(800, 600, 1.0);
+            let repaint = EventResponse {
+                consumed: false,
+                repaint: true,
+            };
+            assert_eq!(state.on_event(&ctx, window, &window_event(window, WindowEvent::FocusGained)), EventResponse::default());
+            state.on_event(&ctx, window, &key(window, Keycode::A, Mod::empty(), true));
+            state.take_raw_input();
+
+            assert_eq!(state.on_event(&ctx, window, &window_event(window, WindowEvent::FocusLost)), repaint);
+            assert_eq!(state.on_event(&ctx, window, &window_event(window, WindowEvent::FocusLost)), EventResponse::default());
+            assert_eq!(key_events(&state.take_raw_input().events), vec![(Key::A, false, Modifiers::default())]);
+
+            assert_eq!(state.on_event(&ctx, window, &window_event(window, WindowEvent::FocusGained)), repaint);
+            assert_eq!(state.on_event(&ctx, window, &window_event(window, WindowEvent::FocusGained)), EventResponse::default());
+        });
+    }
+
+    #[test]
+    fn text_typed_as_shortcut_is_dropped() {
+        with_window(|window| {
+            let ctx = Context::default();
+            let mut state = InputState::new(800, 600, 1.0);
+            let text = |text: &str| Event::TextInput {
+                timestamp: 0,
+                window_id: window.id(),
+                text: text.to_string(),
+            };
+            state.on_event(&ctx, window, &key(window, Keycode::C, Mod::LCTRLMOD, true));
+            state.on_event(&ctx, window, &text("c"));
+            let events = state.take_raw_input().events;
+            assert_eq!(key_events(&events).len(), 1);
+            assert!(events.contains(&egui::Event::Copy));
+            assert!(!events.iter().any(|event| matches!(event, egui::Event::Text(_))));
+
+            // AltGr, which Windows reports as Ctrl+Alt, still types.
+            state.on_event(&ctx, window, &key(window, Keycode::Q, Mod::LCTRLMOD | Mod::RALTMOD, true));
+            state.on_event(&ctx, window, &text("@"));
+            assert!(state.take_raw_input().events.contains(&egui::Event::Text("@".to_string())));
+        });
+    }
+
+    #[test]
+    fn held_keys_and_buttons_follow_presses() {
+        with_window(|window| {
+            let ctx = Context::default();
+            let mut state = InputState::new(800, 600, 1.0);
+            state.on_event(&ctx, window, &key(window, Keycode::A, Mod::empty(), true));
+            state.on_event(&ctx, window, &key(window, Keycode::Left, Mod::empty(), true));
+            state.on_event(&ctx, window, &button_down(window, 10, 10));
+            assert_eq!(state.held_keys(), &HashSet::from([Key::A, Key::ArrowLeft]));
+            assert_eq!(state.held_buttons(), &[PointerButton::Primary]);
+
+            state.on_event(&ctx, window, &key(window, Keycode::A, Mod::empty(), false));
+            assert_eq!(state.held_keys(), &HashSet::from([Key::ArrowLeft]));
+
+            state.on_event(&ctx, window, &window_event(window, WindowEvent::FocusLost));
+            assert!(state.held_keys().is_empty());
+            assert!(state.held_buttons().is_empty());
+        });
+    }
+
+    #[test]
+    fn buttons_keep_sub_point_positions_at_fractional_scale() {
+        with_window(|window| {
+            let ctx = Context::default();
+            let mut state = InputState::new(800, 600, 1.5);
+            state.on_event(&ctx, window, &button_down(window, 100, 101));
+            let pressed_at = state.take_raw_input().events.iter().find_map(|event| match event {
+                egui::Event::PointerButton { pos, .. } => Some(*pos),
+                _ => None,
+            });
+            assert_eq!(pressed_at, Some(egui::pos2(100.0 / 1.5, 101.0 / 1.5)));
+            assert_ne!(pressed_at.unwrap().x.fract(), 0.0);
+        });
+    }
+
+    #[cfg(feature = "test-harness")]
+    #[test]
+    fn run_frame_reports_applied_output() {
+        with_window(|window| {
+            let ctx = Context::default();
+            let mut state = InputState::new(800, 600, 1.0);
+            let (_, applied) = state.run_frame(&ctx, window, &[], |ctx| {
+                ctx.output().copied_text = "copied".to_string();
+                ctx.output().cursor_icon = egui::CursorIcon::Text;
+            });
+            assert_eq!(applied.clipboard_text.as_deref(), Some("copied"));
+            assert_eq!(applied.system_cursor, sdl2::mouse::SystemCursor::IBeam);
+            assert_eq!(applied.text_input_rect, None);
+
+            let mut text = String::new();
+            let mut text_field = |ctx: &Context| {
+                egui::CentralPanel::default().show(ctx, |ui| ui.text_edit_singleline(&mut text).request_focus());
+            };
+            state.run_frame(&ctx, window, &[], &mut text_field);
+            let (output, applied) = state.run_frame(&ctx, window, &[], &mut text_field);
+            assert!(output.platform_output.text_cursor_pos.is_some());
+            assert!(applied.text_input_rect.is_some());
+            assert_eq!(applied.clipboard_text, None);
+        });
+    }
+
+    #[test]
+    fn warp_filter_only_applies_to_the_next_motion() {
+        with_window(|window| {
+            let ctx = Context::default();
+            let mut state = InputState::new(800, 600, 1.0);
+            state.center_cursor(window);
+            state.on_event(&ctx, window, &motion(window, 0, 0, 400, 300));
+            assert!(state.take_raw_input().events.is_empty());
+
+            state.center_cursor(window);
+            state.on_event(&ctx, window, &motion(window, 0, 0, 10, 10));
+            state.on_event(&ctx, window, &motion(window, 0, 0, 400, 300));
+            assert_eq!(
+                state.take_raw_input().events,
+                vec![
+                    egui::Event::PointerMoved(egui::pos2(10.0, 10.0)),
+                    egui::Event::PointerMoved(egui::pos2(400.0, 300.0)),
+                ]
+            );
+        });
+    }
+
+    #[test]
+    fn app_keys_drop_their_text() {
+        with_window(|window| {
+            let ctx = Context::default();
+            let config = InputConfig {
+                app_keys: vec![Keycode::Backquote, Keycode::F1],
+                ..Default::default()
+            };
+            let mut state = InputState::with_config(800, 600, 1.0, config);
+            let text = |text: &str| Event::TextInput {
+                timestamp: 0,
+                window_id: window.id(),
+                text: text.to_string(),
+            };
+            state.on_event(&ctx, window, &key(window, Keycode::Backquote, Mod::empty(), true));
+            state.on_event(&ctx, window, &text("`"));
+            state.on_event(&ctx, window, &key(window, Keycode::Backquote, Mod::empty(), false));
+            assert!(state.take_raw_input().events.is_empty());
+
+            // F1 types nothing, so the text after it is a real one.
+            state.on_event(&ctx, window, &key(window, Keycode::F1, Mod::empty(), true));
+            state.on_event(&ctx, window, &text("x"));
+            assert_eq!(state.take_raw_input().events, vec![egui::Event::Text("x".to_string())]);
+        });
+    }
+
+    #[test]
+    fn unrelated_events_are_ignored() {
+        with_window(|window| {
+            let ctx = Context::default();
+            let mut state = InputState::new(800, 600, 1.0);
+            let timestamp = 0;
+            let events = [
+                Event::Quit { timestamp },
+                Event::ClipboardUpdate { timestamp },
+                Event::AudioDeviceAdded { timestamp, which: 0, iscapture: false },
+                Event::JoyAxisMotion { timestamp, which: 0, axis_idx: 0, value: 100 },
+                Event::JoyDeviceAdded { timestamp, which: 0 },
+                Event::ControllerDeviceAdded { timestamp, which: 0 },
+                Event::DropBegin { timestamp, window_id: window.id() },
+                Event::DropComplete { timestamp, window_id: window.id() },
+                Event::User {
+                    timestamp,
+                    window_id: window.id(),
+                    type_: 0x8000,
+                    code: 0,
+                    data1: std::ptr::null_mut(),
+                    data2: std::ptr::null_mut(),
+                },
+            ];
+            for event in &events {
+                assert_eq!(state.on_event(&ctx, window, event), EventResponse::default(), "{:?}", event);
+            }
+            assert!(state.take_raw_input().events.is_empty());
+        });
+    }
+
+    #[test]
+    fn input_events_are_translated() {
+        with_window(|window| {
+            let ctx = Context::default();
+            let mut state = InputState::new(800, 600, 1.0);
+            let text = Event::TextInput {
+                timestamp: 0,
+                window_id: window.id(),
+                text: "a".to_string(),
+            };
+            let events = [
+                motion(window, 0, 0, 10, 10),
+                button_down(window, 10, 10),
+                key(window, Keycode::Left, Mod::empty(), true),
+                text,
+            ];
+            for event in &events {
+                assert!(state.on_event(&ctx, window, event).repaint, "{:?}", event);
+            }
+            assert!(state.take_raw_input().events.len() >= events.len());
+        });
+    }
+
+    #[test]
+    fn process_and_run_keeps_an_earlier_repaint_request() {
+        with_window(|window| {
+            let ctx = Context::default();
+            let mut state = InputState::new(800, 600, 1.0);
+            // Let egui settle until an empty run no longer asks for a repaint.
+            let settled = (0..10).any(|_| {
+                let mut output = None;
+                state.process_and_run(&ctx, window, None, 1.0 / 60.0, &mut output, |_| {});
+                !output.unwrap().needs_repaint
+            });
+            assert!(settled);
+            let mut pending = None;
+            state.process_and_run(&ctx, window, None, 1.0 / 60.0, &mut pending, |ctx| ctx.request_repaint());
+            state.process_and_run(&ctx, window, None, 1.0 / 60.0, &mut pending, |_| {});
+            assert!(pending.unwrap().needs_repaint);
+        });
+    }
+
+    #[test]
+    fn simulated_resize_keeps_the_drawable_scale() {
+        let mut state = InputState::new(800, 600, 1.0);
+        state.update_geometry((1600, 1200), (800, 600), 1.5);
+        state.simulate_resize(1000, 700);
+        assert_eq!(state.drawable_size(), (2000, 1400));
+        assert_eq!(state.pixels_per_point(), 3.0);
+        assert_eq!(state.screen_rect().size(), egui::vec2(1000.0, 700.0) / 1.5);
+    }
+
+    #[test]
+    fn clipboard_keys_map_without_ctrl() {
+        with_window(|window| {
+            let ctx = Context::default();
+            let mut state = InputState::new(800, 600, 1.0);
+            state.on_event(&ctx, window, &key(window, Keycode::Copy, Mod::empty(), true));
+            assert_eq!(state.take_raw_input().events, vec![egui::Event::Copy]);
+            state.on_event(&ctx, window, &key(window, Keycode::Cut, Mod::empty(), true));
+            assert_eq!(state.take_raw_input().events, vec![egui::Event::Cut]);
+
+            state.on_event(&ctx, window, &key(window, Keycode::C, Mod::empty(), true));
+            let events = state.take_raw_input().events;
+            assert!(!events.contains(&egui::Event::Copy) && !events.contains(&egui::Event::Cut));
+            assert_eq!(key_events(&events), vec![(Key::C, true, Modifiers::default())]);
+        });
+    }
+
+    #[test]
+    fn hiding_and_showing_the_window_tracks_visibility() {
+        with_window(|window| {
+            let ctx = Context::default();
+            let mut state = InputState::new(800, 600, 1.0);
+            assert!(state.is_visible());
+            for (hide, show) in [(WindowEvent::Hidden, WindowEvent::Shown), (WindowEvent::Minimized, WindowEvent::Restored)] {
+                state.on_event(&ctx, window, &window_event(window, hide));
+                assert!(!state.is_visible());
+                state.on_event(&ctx, window, &window_event(window, show));
+                assert!(state.is_visible());
+            }
+        });
+    }
+
+    #[test]
+    fn input_and_render_scale_agree_after_a_dpi_change() {
+        let mut state = InputState::new(800, 600, 1.0);
+        state.update_geometry((1600, 1200), (800, 600), 1.0);
+        state.take_raw_input();
+        state.simulate_dpi(2.0);
+        let raw_input = state.take_raw_input();
+        assert_eq!(raw_input.pixels_per_point, Some(state.pixels_per_point()));
+        assert_eq!(state.pixels_per_point(), 4.0);
+        let screen_rect = egui::Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(400.0, 300.0));
+        assert_eq!(raw_input.screen_rect, Some(screen_rect));
+        assert_eq!(state.screen_rect(), screen_rect);
+    }
+
+    #[test]
+    fn same_position_clicks_are_all_forwarded() {
+        with_window(|window| {
+            let ctx = Context::default();
+            let mut state = InputState::new(800, 600, 1.0);
+            let (timestamp, window_id, which, mouse_btn, x, y) = (0, window.id(), 0, MouseButton::Left, 10, 10);
+            for clicks in [1, 2] {
+                state.on_event(&ctx, window, &Event::MouseButtonDown { timestamp, window_id, which, mouse_btn, clicks, x, y });
+                state.on_event(&ctx, window, &Event::MouseButtonUp { timestamp, window_id, which, mouse_btn, clicks, x, y });
+            }
+            let buttons: Vec<_> = state
+                .take_raw_input()
+                .events
+                .into_iter()
+                .filter_map(|event| match event {
+                    egui::Event::PointerButton { pos, button, pressed, .. } => Some((pos, button, pressed)),
+                    _ => None,
+                })
+                .collect();
+            let pos = egui::pos2(10.0, 10.0);
+            let press = (pos, PointerButton::Primary, true);
+            let release = (pos, PointerButton::Primary, false);
+            assert_eq!(buttons, vec![press, release, press, release]);
+        });
+    }
+
+    #[test]
+    fn stale_resize_sizes_follow_the_window() {
+        with_window(|window| {
+            let ctx = Context::default();
+            let mut state = InputState::new(640, 480, 1.0);
+            state.on_event(&ctx, window, &window_event(window, WindowEvent::Resized(1024, 768)));
+            state.on_event(&ctx, window, &window_event(window, WindowEvent::SizeChanged(300, 200)));
+            assert_eq!(window.size(), (800, 600));
+            let screen_rect = egui::Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(800.0, 600.0));
+            assert_eq!(state.screen_rect(), screen_rect);
+            assert_eq!(state.take_raw_input().screen_rect, Some(screen_rect));
+        });
+    }
+
+    #[test]
+    fn ctrl_backspace_and_shift_enter_are_forwarded() {
+        with_window(|window| {
+            let ctx = Context::default();
+            let mut state = InputState::new(800, 600, 1.0);
+            state.on_event(&ctx, window, &key(window, Keycode::Backspace, Mod::LCTRLMOD, true));
+            state.on_event(&ctx, window, &key(window, Keycode::Return, Mod::LSHIFTMOD, true));
+            let ctrl = Modifiers {
+                ctrl: true,
+                command: true,
+                ..Default::default()
+            };
+            let shift = Modifiers {
+                shift: true,
+                ..Default::default()
+            };
+            assert_eq!(
+                key_events(&state.take_raw_input().events),
+                vec![(Key::Backspace, true, ctrl), (Key::Enter, true, shift)]
+            );
+        });
+    }
+
+    #[test]
+    fn ctrl_and_shift_arrows_are_forwarded() {
+        with_window(|window| {
+            let ctx = Context::default();
+            let mut state = InputState::new(800, 600, 1.0);
+            state.on_event(&ctx, window, &key(window, Keycode::Left, Mod::LCTRLMOD, true));
+            state.on_event(&ctx, window, &key(window, Keycode::Right, Mod::LCTRLMOD | Mod::LSHIFTMOD, true));
+            state.on_event(&ctx, window, &key(window, Keycode::Up, Mod::RSHIFTMOD, true));
+            let ctrl = Modifiers {
+                ctrl: true,
+                command: true,
+                ..Default::default()
+            };
+            let ctrl_shift = Modifiers { shift: true, ..ctrl };
+            let shift = Modifiers {
+                shift: true,
+                ..Default::default()
+            };
+            assert_eq!(
+                key_events(&state.take_raw_input().events),
+                vec![(Key::ArrowLeft, true, ctrl), (Key::ArrowRight, true, ctrl_shift), (Key::ArrowUp, true, shift)]
+            );
+        });
+    }
+
+    #[test]
+    fn key_up_is_a_release() {
+        with_window(|window| {
+            let ctx = Context::default();
+            let mut state = InputState::new(800, 600, 1.0);
+            state.on_event(&ctx, window, &key(window, Keycode::Escape, Mod::empty(), true));
+            state.take_raw_input();
+            state.on_event(&ctx, window, &key(window, Keycode::Escape, Mod::empty(), false));
+            assert_eq!(key_events(&state.take_raw_input().events), vec![(Key::Escape, false, Modifiers::default())]);
+        });
+    }
+
+    // SDL keycodes already follow the layout: on QWERTZ the key labelled Z reports `Keycode::Z`
+    // even though it sits where QWERTY has Y, so egui gets the key the user pressed.
+    #[test]
+    fn undo_and_redo_shortcuts_keep_command() {
+        with_window(|window| {
+            let ctx = Context::default();
+            let mut state = InputState::new(800, 600, 1.0);
+            state.on_event(&ctx, window, &key(window, Keycode::Z, Mod::LCTRLMOD, true));
+            state.on_event(&ctx, window, &key(window, Keycode::Y, Mod::LCTRLMOD, true));
+            state.on_event(&ctx, window, &key(window, Keycode::Z, Mod::LCTRLMOD | Mod::LSHIFTMOD, true));
+            let ctrl = Modifiers {
+                ctrl: true,
+                command: true,
+                ..Default::default()
+            };
+            let ctrl_shift = Modifiers { shift: true, ..ctrl };
+            assert_eq!(
+                key_events(&state.take_raw_input().events),
+                vec![(Key::Z, true, ctrl), (Key::Y, true, ctrl), (Key::Z, true, ctrl_shift)]
+            );
+        });
+    }
+
+    #[test]
+    fn caps_lock_is_tracked_without_key_or_text_events() {
+        with_window(|window| {
+            let ctx = Context::default();
+            let mut state = InputState::new(800, 600, 1.0);
+            state.on_event(&ctx, window, &key(window, Keycode::CapsLock, Mod::CAPSMOD, true));
+            assert!(state.is_caps_lock_held());
+            state.on_event(&ctx, window, &key(window, Keycode::CapsLock, Mod::CAPSMOD, false));
+            assert!(!state.is_caps_lock_held());
+            let events = state.take_raw_input().events;
+            assert!(!events.iter().any(|event| matches!(event, egui::Event::Key { .. } | egui::Event::Text(_))));
+        });
+    }
+
+    #[test]
+    fn unfocusing_cancels_the_composition() {
+        with_window(|window| {
+            let ctx = Context::default();
+            let mut state = InputState::new(800, 600, 1.0);
+            let focused = PlatformOutput {
+                text_cursor_pos: Some(egui::pos2(10.0, 10.0)),
+                ..Default::default()
+            };
+            state.process_output(window, &focused);
+            let editing = Event::TextEditing {
+                timestamp: 0,
+                window_id: window.id(),
+                text: "ka".to_string(),
+                start: 2,
+                length: 0,
+            };
+            state.on_event(&ctx, window, &editing);
+            assert!(state.composing);
+
+            state.process_output(window, &PlatformOutput::default());
+            assert!(!state.composing);
+            assert!(state.is_text_input_active(window));
+        });
+    }
+}
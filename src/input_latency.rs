@@ -0,0 +1,52 @@
+use std::time::Duration;
+use sdl2::event::Event;
+
+// Measures the time between SDL receiving an event and the egui frame that consumed it
+// finishing. Starting from SDL's event timestamp, rather than from when the event was polled,
+// counts the time events wait in SDL's queue for the next frame, which is what running egui
+// as events arrive cuts down. SDL's timestamps are in whole milliseconds.
+pub struct InputLatency {
+    pending: Vec<u32>,
+    total_ms: u64,
+    samples: u32,
+}
+
+impl InputLatency {
+    pub fn new() -> InputLatency {
+        InputLatency {
+            pending: Vec::new(),
+            total_ms: 0,
+            samples: 0,
+        }
+    }
+
+    fn time_now(&self) -> u32 {
+        #[allow(unsafe_code)]
+        return unsafe { sdl2::sys::SDL_GetTicks() };
+    }
+
+    pub fn event_translated(&mut self, event: &Event) {
+        self.pending.push(event.get_timestamp());
+    }
+
+    pub fn frame_processed(&mut self) {
+        let now = self.time_now();
+        for received in self.pending.drain(..) {
+            self.total_ms += now.wrapping_sub(received) as u64;
+            self.samples += 1;
+        }
+    }
+
+    // Starts a new average, e.g. after switching between buffered and immediate processing.
+    pub fn reset(&mut self) {
+        self.total_ms = 0;
+        self.samples = 0;
+    }
+
+    pub fn average(&self) -> Option<Duration> {
+        if self.samples == 0 {
+            return None;
+        }
+        Some(Duration::from_secs_f64(self.total_ms as f64 / self.samples as f64 / 1000.0))
+    }
+}
@@ -0,0 +1,214 @@
+use egui::Key;
+use sdl2::keyboard::{Keycode, Mod};
+
+// Every SDL keycode that has an egui `Key`. Number keys map from both the main row and the keypad,
+// as egui doesn't tell them apart. Keys that only type text (punctuation etc.) have no `Key`
+// variant in egui 0.18 and reach egui through `TextInput` instead.
+pub const KEY_MAP: &[(Keycode, Key)] = &[
+    (Keycode::Left, Key::ArrowLeft),
+    (Keycode::Up, Key::ArrowUp),
+    (Keycode::Right, Key::ArrowRight),
+    (Keycode::Down, Key::ArrowDown),
+    (Keycode::Escape, Key::Escape),
+    (Keycode::Tab, Key::Tab),
+    (Keycode::Backspace, Key::Backspace),
+    (Keycode::Space, Key::Space),
+    (Keycode::Return, Key::Enter),
+    (Keycode::KpEnter, Key::Enter),
+    (Keycode::Insert, Key::Insert),
+    (Keycode::Home, Key::Home),
+    (Keycode::Delete, Key::Delete),
+    (Keycode::End, Key::End),
+    (Keycode::PageDown, Key::PageDown),
+    (Keycode::PageUp, Key::PageUp),
+    (Keycode::Num0, Key::Num0),
+    (Keycode::Kp0, Key::Num0),
+    (Keycode::Num1, Key::Num1),
+    (Keycode::Kp1, Key::Num1),
+    (Keycode::Num2, Key::Num2),
+    (Keycode::Kp2, Key::Num2),
+    (Keycode::Num3, Key::Num3),
+    (Keycode::Kp3, Key::Num3),
+    (Keycode::Num4, Key::Num4),
+    (Keycode::Kp4, Key::Num4),
+    (Keycode::Num5, Key::Num5),
+    (Keycode::Kp5, Key::Num5),
+    (Keycode::Num6, Key::Num6),
+    (Keycode::Kp6, Key::Num6),
+    (Keycode::Num7, Key::Num7),
+    (Keycode::Kp7, Key::Num7),
+    (Keycode::Num8, Key::Num8),
+    (Keycode::Kp8, Key::Num8),
+    (Keycode::Num9, Key::Num9),
+    (Keycode::Kp9, Key::Num9),
+    (Keycode::A, Key::A),
+    (Keycode::B, Key::B),
+    (Keycode::C, Key::C),
+    (Keycode::D, Key::D),
+    (Keycode::E, Key::E),
+    (Keycode::F, Key::F),
+    (Keycode::G, Key::G),
+    (Keycode::H, Key::H),
+    (Keycode::I, Key::I),
+    (Keycode::J, Key::J),
+    (Keycode::K, Key::K),
+    (Keycode::L, Key::L),
+    (Keycode::M, Key::M),
+    (Keycode::N, Key::N),
+    (Keycode::O, Key::O),
+    (Keycode::P, Key::P),
+    (Keycode::Q, Key::Q),
+    (Keycode::R, Key::R),
+    (Keycode::S, Key::S),
+    (Keycode::T, Key::T),
+    (Keycode::U, Key::U),
+    (Keycode::V, Key::V),
+    (Keycode::W, Key::W),
+    (Keycode::X, Key::X),
+    (Keycode::Y, Key::Y),
+    (Keycode::Z, Key::Z),
+];
+
+pub fn translate_key(keycode: Keycode) -> Option<Key> {
+    KEY_MAP
+        .iter()
+        .find(|(sdl_key, _)| *sdl_key == keycode)
+        .map(|(_, key)| *key)
+}
+
+// Keys that also produce a `TextInput` character when pressed without shortcut modifiers.
+pub fn is_printable(key: Key) -> bool {
+    use Key::*;
+    matches!(
+        key,
+        Space
+            | Num0 | Num1 | Num2 | Num3 | Num4 | Num5 | Num6 | Num7 | Num8 | Num9
+            | A | B | C | D | E | F | G | H | I | J | K | L | M
+            | N | O | P | Q | R | S | T | U | V | W | X | Y | Z
+    )
+}
+
+// SDL keycodes of keys that type a character are that character, every other key's is derived
+// from its scancode with bit 30 set. Unlike `is_printable` this covers punctuation too.
+pub fn types_text(keycode: Keycode) -> bool {
+    let code = keycode as i32;
+    code >= 0x20 && code != Keycode::Delete as i32 && code & (1 << 30) == 0
+}
+
+// Which side's modifier keys are held. egui's `Modifiers` folds both sides together; this is for
+// apps with side-specific bindings.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ModifierSides {
+    pub left_shift: bool,
+    pub right_shift: bool,
+    pub left_ctrl: bool,
+    pub right_ctrl: bool,
+    pub left_alt: bool,
+    pub right_alt: bool,
+    pub left_gui: bool,
+    pub right_gui: bool,
+}
+
+impl ModifierSides {
+    pub fn from_sdl(keymod: Mod) -> ModifierSides {
+        ModifierSides {
+            left_shift: keymod.contains(Mod::LSHIFTMOD),
+            right_shift: keymod.contains(Mod::RSHIFTMOD),
+            left_ctrl: keymod.contains(Mod::LCTRLMOD),
+            right_ctrl: keymod.contains(Mod::RCTRLMOD),
+            left_alt: keymod.contains(Mod::LALTMOD),
+            right_alt: keymod.contains(Mod::RALTMOD),
+            left_gui: keymod.contains(Mod::LGUIMOD),
+            right_gui: keymod.contains(Mod::RGUIMOD),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Written out independently of `KEY_MAP`, so removing or changing an entry there fails here.
+    const COMMON_KEYS: &[(Keycode, Key)] = &[
+        (Keycode::Left, Key::ArrowLeft),
+        (Keycode::Up, Key::ArrowUp),
+        (Keycode::Right, Key::ArrowRight),
+        (Keycode::Down, Key::ArrowDown),
+        (Keycode::Escape, Key::Escape),
+        (Keycode::Tab, Key::Tab),
+        (Keycode::Backspace, Key::Backspace),
+        (Keycode::Space, Key::Space),
+        (Keycode::Return, Key::Enter),
+        (Keycode::Insert, Key::Insert),
+        (Keycode::Delete, Key::Delete),
+        (Keycode::Home, Key::Home),
+        (Keycode::End, Key::End),
+        (Keycode::PageUp, Key::PageUp),
+        (Keycode::PageDown, Key::PageDown),
+        (Keycode::Num0, Key::Num0),
+        (Keycode::Num9, Key::Num9),
+        (Keycode::A, Key::A),
+        (Keycode::C, Key::C),
+        (Keycode::V, Key::V),
+        (Keycode::X, Key::X),
+        (Keycode::Z, Key::Z),
+    ];
+
+    #[test]
+    fn common_keys_are_mapped() {
+        for (keycode, key) in COMMON_KEYS {
+            assert_eq!(translate_key(*keycode), Some(*key), "{:?}", keycode);
+        }
+    }
+
+    #[test]
+    fn every_mapped_keycode_translates_to_its_key() {
+        for (keycode, key) in KEY_MAP {
+            assert_eq!(translate_key(*keycode), Some(*key), "{:?}", keycode);
+        }
+    }
+
+    #[test]
+    fn each_keycode_is_mapped_once() {
+        for (i, (keycode, _)) in KEY_MAP.iter().enumerate() {
+            assert!(KEY_MAP[i + 1..].iter().all(|(other, _)| other != keycode), "{:?}", keycode);
+        }
+    }
+
+    #[test]
+    fn keypad_digits_map_like_the_number_row() {
+        let pairs = [
+            (Keycode::Kp0, Keycode::Num0),
+            (Keycode::Kp1, Keycode::Num1),
+            (Keycode::Kp2, Keycode::Num2),
+            (Keycode::Kp3, Keycode::Num3),
+            (Keycode::Kp4, Keycode::Num4),
+            (Keycode::Kp5, Keycode::Num5),
+            (Keycode::Kp6, Keycode::Num6),
+            (Keycode::Kp7, Keycode::Num7),
+            (Keycode::Kp8, Keycode::Num8),
+            (Keycode::Kp9, Keycode::Num9),
+            (Keycode::KpEnter, Keycode::Return),
+        ];
+        for (keypad, main) in pairs {
+            assert_eq!(translate_key(keypad), translate_key(main), "{:?}", keypad);
+        }
+    }
+
+    #[test]
+    fn text_only_keys_have_no_key() {
+        for keycode in [Keycode::Comma, Keycode::Period, Keycode::Minus, Keycode::F1, Keycode::LShift] {
+            assert_eq!(translate_key(keycode), None, "{:?}", keycode);
+        }
+    }
+
+    #[test]
+    fn types_text_covers_characters_only() {
+        for keycode in [Keycode::A, Keycode::Num1, Keycode::Space, Keycode::Comma, Keycode::Backquote] {
+            assert!(types_text(keycode), "{:?}", keycode);
+        }
+        for keycode in [Keycode::F1, Keycode::Return, Keycode::Escape, Keycode::Delete, Keycode::Left, Keycode::LCtrl] {
+            assert!(!types_text(keycode), "{:?}", keycode);
+        }
+    }
+}
@@ -1,5 +1,17 @@
+mod cursor;
 mod frame_timer;
+#[cfg(feature = "gamepad")]
+mod gamepad;
+mod input;
+mod keys;
+mod resize_debounce;
+mod scissor;
+mod texture_history;
+mod touch;
+#[cfg(feature = "input-latency")]
+mod input_latency;
 use std::sync::Arc;
+use std::time::Duration;
 use sdl2::{Sdl, VideoSubsystem};
 use sdl2::event::{Event, WindowEvent};
 use sdl2::keyboard::{Keycode};
@@ -10,11 +22,22 @@ use egui::{Rgba};
 use egui::mutex::RwLock;
 use egui_wgpu::renderer;
 use egui_wgpu::renderer::RenderPass;
-use egui_sdl2_event::EguiSDL2State;
 use crate::frame_timer::FrameTimer;
+#[cfg(feature = "gamepad")]
+use crate::gamepad::Gamepads;
+use crate::input::InputState;
+use crate::resize_debounce::ResizeDebounce;
+use crate::texture_history::TextureHistory;
+#[cfg(feature = "input-latency")]
+use crate::input_latency::InputLatency;
 
 const INITIAL_WIDTH: u32 = 800;
 const INITIAL_HEIGHT: u32 = 600;
+// How long the window size must stay unchanged before the surface is reconfigured.
+const RESIZE_DEBOUNCE: Duration = Duration::from_millis(0);
+// With "Run egui as events arrive", how far into a frame, in milliseconds, input is still run
+// through egui as it arrives before the frame is painted.
+const EVENT_WINDOW_MS: u32 = 8;
 
 struct WGPUSDL2 {
     sdl_window: Window,
@@ -26,51 +49,103 @@ struct WGPUSDL2 {
     surface_config: SurfaceConfiguration,
 }
 
-fn init_sdl(width: u32, height: u32) -> WGPUSDL2 {
-    let sdl_context = sdl2::init().expect("Cannot initialize SDL2!");
-    let video_subsystem = sdl_context.video().expect("Cannot get SDL2 context!");
+struct InitConfig {
+    // wgpu 0.12 checks this against the surface's supported modes when configuring and falls
+    // back to Fifo, which every surface supports, if it is unavailable.
+    present_mode: wgpu::PresentMode,
+    power_preference: wgpu::PowerPreference,
+    backends: wgpu::Backends,
+}
+
+impl Default for InitConfig {
+    fn default() -> Self {
+        InitConfig {
+            present_mode: wgpu::PresentMode::Mailbox,
+            power_preference: wgpu::PowerPreference::HighPerformance,
+            backends: wgpu::Backends::PRIMARY,
+        }
+    }
+}
+
+// wgpu 0.12's `get_preferred_format` already returns the first format the surface supports out
+// of Bgra8UnormSrgb, Rgba8UnormSrgb, Bgra8Unorm and Rgba8Unorm, so sRGB is preferred whenever
+// it is available. A UNORM result means neither sRGB format is supported, and swapping in one
+// anyway would fail surface configuration; the result is used as is. `None` means the surface
+// supports none of these formats, and there is nothing safe to guess.
+// Newer wgpu releases replace this with `surface.get_capabilities(&adapter).formats`, which can
+// be swapped in here.
+// On a UNORM surface egui-wgpu, given the same format for its `RenderPass`, only switches the
+// vertex entry point to `vs_conv_main`, which passes vertex colors through still sRGB-encoded
+// instead of decoding them. Textures are still sampled from `Rgba8UnormSrgb` (decoded to linear)
+// and written without re-encoding, and blending happens on the encoded values, so text and
+// images come out darker and edges blend differently than on an sRGB surface.
+fn select_surface_format(surface: &Surface, adapter: &wgpu::Adapter) -> Option<wgpu::TextureFormat> {
+    surface.get_preferred_format(adapter)
+}
+
+// Blocks until SDL has an event queued or `timeout_ms` passed. The event is left in the queue for
+// the next `poll_iter`.
+fn wait_for_event(timeout_ms: u32) -> bool {
+    #[allow(unsafe_code)]
+    unsafe { sdl2::sys::SDL_WaitEventTimeout(std::ptr::null_mut(), timeout_ms as i32) == 1 }
+}
+
+fn init_sdl(width: u32, height: u32, init_config: &InitConfig) -> WGPUSDL2 {
+    match try_init_sdl(width, height, init_config) {
+        Ok(sys) => sys,
+        Err(e) => panic!("{}", e),
+    }
+}
+
+fn try_init_sdl(width: u32, height: u32, init_config: &InitConfig) -> Result<WGPUSDL2, String> {
+    let sdl_context = sdl2::init().map_err(|e| format!("Cannot initialize SDL2: {}", e))?;
+    let video_subsystem = sdl_context.video().map_err(|e| format!("Cannot get SDL2 context: {}", e))?;
     let window = video_subsystem
         .window("egui-sdl2-event-example", width, height)
         .position_centered()
         .resizable()
         .build()
-        .map_err(|e| e.to_string()).expect("Cannot create SDL2 window!");
+        .map_err(|e| format!("Cannot create SDL2 window: {}", e))?;
 
-    let instance = wgpu::Instance::new(wgpu::Backends::PRIMARY);
+    let instance = wgpu::Instance::new(init_config.backends);
     #[allow(unsafe_code)]
         let surface = unsafe { instance.create_surface(&window) };
-    let adapter_opt = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
-        power_preference: wgpu::PowerPreference::HighPerformance,
-        force_fallback_adapter: false,
-        compatible_surface: Some(&surface),
-    }));
-    let adapter = match adapter_opt {
-        Some(a) => { a },
-        None => panic!("Failed to find wgpu adapter!") ,
+    let request_adapter = |force_fallback_adapter| {
+        pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference: init_config.power_preference,
+            force_fallback_adapter,
+            compatible_surface: Some(&surface),
+        }))
+    };
+    // Fall back to a software adapter, if the platform has one, before giving up.
+    let adapter = match request_adapter(false).or_else(|| request_adapter(true)) {
+        Some(a) => a,
+        None => return Err("Failed to find a compatible wgpu adapter!".to_string()),
     };
 
-    let (device, queue) = match pollster::block_on(adapter.request_device(
+    let (device, queue) = pollster::block_on(adapter.request_device(
         &wgpu::DeviceDescriptor {
             limits: wgpu::Limits::default(),
             label: Some("device"),
             features: wgpu::Features::empty(),
         },
         None,
-    )) {
-        Ok(a) => a,
-        Err(e) => panic!("{}", e.to_string()),
-    };
+    )).map_err(|e| format!("Cannot create wgpu device: {}", e))?;
 
+    // The surface has to match the drawable size, which on HiDPI windows can be larger than the
+    // requested (logical) size. egui's screen rect is still computed from the logical size.
+    let (drawable_width, drawable_height) = window.drawable_size();
     let config = wgpu::SurfaceConfiguration {
         usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
-        format: surface.get_preferred_format(&adapter).unwrap(),
-        width,
-        height,
-        present_mode: wgpu::PresentMode::Mailbox,
+        format: select_surface_format(&surface, &adapter)
+            .ok_or_else(|| "The surface supports none of the usual 8-bit RGBA formats".to_string())?,
+        width: drawable_width,
+        height: drawable_height,
+        present_mode: init_config.present_mode,
     };
     surface.configure(&device, &config);
 
-    WGPUSDL2 {
+    Ok(WGPUSDL2 {
         sdl_context: sdl_context,
         sdl_video_subsystem: video_subsystem,
         sdl_window: window,
@@ -78,9 +153,25 @@ fn init_sdl(width: u32, height: u32) -> WGPUSDL2 {
         surface_config: config,
         device: device,
         queue: queue
+    })
+}
+
+fn update_textures(device: &Device, queue: &Queue, egui_rpass: &mut RenderPass, textures_delta: &egui::TexturesDelta) {
+    for (id, image_delta) in &textures_delta.set {
+        egui_rpass.update_texture(device, queue, *id, image_delta);
+    }
+}
+
+fn free_textures(egui_rpass: &mut RenderPass, textures_delta: &egui::TexturesDelta) {
+    for id in &textures_delta.free {
+        egui_rpass.free_texture(id);
     }
 }
 
+// Textures are uploaded and freed even when no frame can be acquired, since egui sends each
+// texture delta only once and a skipped delta would leave glyphs or images missing for good.
+// Only running out of memory is reported back; every other surface error skips this frame and
+// the next one tries again.
 fn paint_and_update_textures(
     device: &Device,
     queue: &Queue,
@@ -91,13 +182,25 @@ fn paint_and_update_textures(
     clear_color: egui::Rgba,
     clipped_primitives: &[egui::ClippedPrimitive],
     textures_delta: &egui::TexturesDelta,
-) {
+) -> Result<(), wgpu::SurfaceError> {
+    update_textures(device, queue, &mut egui_rpass.write(), textures_delta);
+
     let output_frame = match surface.get_current_texture() {
         Ok(frame) => frame,
-        Err(wgpu::SurfaceError::Outdated) => {
-            return;
+        Err(e) => {
+            free_textures(&mut egui_rpass.write(), textures_delta);
+            return match e {
+                // The GPU didn't hand out a frame in time; this is usually transient.
+                wgpu::SurfaceError::Timeout => Ok(()),
+                // The surface no longer matches the window or was lost altogether, e.g. after a
+                // display change. Reconfiguring it is enough to get frames again.
+                wgpu::SurfaceError::Outdated | wgpu::SurfaceError::Lost => {
+                    surface.configure(device, surface_config);
+                    Ok(())
+                }
+                wgpu::SurfaceError::OutOfMemory => Err(e),
+            };
         }
-        Err(_) => { return; }
     };
     let output_view = output_frame
         .texture
@@ -114,19 +217,12 @@ fn paint_and_update_textures(
         pixels_per_point,
     };
 
-    {
-        let mut rpass = egui_rpass.write();
-        for (id, image_delta) in &textures_delta.set {
-            rpass.update_texture(&device, &queue, *id, image_delta);
-        }
-
-        rpass.update_buffers(
-            &device,
-            &queue,
-            clipped_primitives,
-            &screen_descriptor,
-        );
-    }
+    egui_rpass.write().update_buffers(
+        &device,
+        &queue,
+        clipped_primitives,
+        &screen_descriptor,
+    );
 
     // Record all render passes.
     egui_rpass.read().execute(
@@ -142,106 +238,194 @@ fn paint_and_update_textures(
         }),
     );
 
-    {
-        let mut rpass = egui_rpass.write();
-        for id in &textures_delta.free {
-            rpass.free_texture(id);
-        }
-    }
+    free_textures(&mut egui_rpass.write(), textures_delta);
 
     // Submit the commands.
     queue.submit(std::iter::once(encoder.finish()));
 
     // Redraw egui
     output_frame.present();
+    Ok(())
 }
 
 
+fn ui_fonts(monospace: bool) -> egui::FontDefinitions {
+    let mut fonts = egui::FontDefinitions::default();
+    if monospace {
+        if let Some(proportional) = fonts.families.get_mut(&egui::FontFamily::Proportional) {
+            proportional.insert(0, "Hack".to_owned());
+        }
+    }
+    fonts
+}
+
 fn main() {
-    let mut sys = init_sdl(INITIAL_WIDTH, INITIAL_HEIGHT);
+    let mut sys = init_sdl(INITIAL_WIDTH, INITIAL_HEIGHT, &InitConfig::default());
     let mut event_pump = sys.sdl_context.event_pump().expect("Cannot create SDL2 event pump");
 
     let egui_ctx = egui::Context::default();
-    let egui_rpass = Arc::new(RwLock::new(RenderPass::new(&sys.device, sys.surface_config.format, 1)));
+    let mut egui_rpass = Arc::new(RwLock::new(RenderPass::new(&sys.device, sys.surface_config.format, 1)));
 
+    let mut texture_history = TextureHistory::new();
     let mut frame_timer = FrameTimer::new();
+    let mut resize_debounce = ResizeDebounce::new(RESIZE_DEBOUNCE);
+    #[cfg(feature = "gamepad")]
+    let mut gamepads = Gamepads::new(sys.sdl_context.game_controller().expect("Cannot get SDL2 game controller subsystem!"));
+    #[cfg(feature = "input-latency")]
+    let mut input_latency = InputLatency::new();
+    #[cfg(feature = "egui-demo")]
+    let mut demo_windows = egui_demo_lib::DemoWindows::default();
 
-    let mut egui_sdl2_state = EguiSDL2State::new(INITIAL_WIDTH, INITIAL_HEIGHT, 1.0);
+    let mut input_state = InputState::new(INITIAL_WIDTH, INITIAL_HEIGHT, 1.0);
+    input_state.sync_focus(&sys.sdl_window);
+    input_state.sync_geometry(&sys.sdl_window);
+    input_state.sync_refresh_rate(&sys.sdl_window);
+    // Let egui use textures as large as the device allows instead of its portable default.
+    input_state.set_max_texture_side(sys.device.limits().max_texture_dimension_2d as usize);
 
     let mut running_time: f64 = 0.0;
     let mut checkbox1_checked = false;
+    let mut monospace_ui = false;
+    let mut run_egui_as_events_arrive = false;
     'running: loop {
         frame_timer.time_start();
         let delta = frame_timer.delta();
         running_time += delta as f64;
 
-        egui_sdl2_state.update_time(Some(running_time), delta);
-
-        for event in event_pump.poll_iter() {
-            match &event {
-                Event::Quit { .. }
-                | Event::KeyDown {
-                    keycode: Some(Keycode::Escape),
-                    ..
-                } => {
-                    break 'running;
-                }
-                Event::Window {
-                    window_id,
-                    win_event: WindowEvent::SizeChanged(width, height) | WindowEvent::Resized(width, height),
-                    ..
-                } => {
-                    if window_id.clone() == sys.sdl_window.id() {
-                        let config = &mut sys.surface_config;
-                        config.width = *width as u32;
-                        config.height = *height as u32;
-                        sys.surface.configure(&sys.device, &config);
+        let mut pending = None;
+        loop {
+            for event in event_pump.poll_iter() {
+                #[cfg(feature = "gamepad")]
+                gamepads.on_event(&event);
+                match &event {
+                    Event::Quit { .. }
+                    | Event::KeyDown {
+                        keycode: Some(Keycode::Escape),
+                        ..
+                    } => {
+                        if let Err(e) = input_state.flush_clipboard(&sys.sdl_window) {
+                            eprintln!("Unable to flush clipboard: {}", e);
+                        }
+                        break 'running;
+                    }
+                    Event::Window {
+                        window_id,
+                        win_event: WindowEvent::SizeChanged(..) | WindowEvent::Resized(..),
+                        ..
+                    } => {
+                        if window_id.clone() == sys.sdl_window.id() {
+                            let (width, height) = sys.sdl_window.drawable_size();
+                            resize_debounce.resized(width, height);
+                        }
                     }
+                    _ => {}
                 }
-                _ => {}
+                input_state.on_event(&egui_ctx, &sys.sdl_window, &event);
+                #[cfg(feature = "input-latency")]
+                input_latency.event_translated(&event);
             }
-            egui_sdl2_state.sdl2_input_to_egui(&sys.sdl_window, &event)
-        }
 
-        let full_output = egui_ctx.run(egui_sdl2_state.raw_input.take(), |ctx| {
-            egui::Window::new("Settings").resizable(true).vscroll(true).show(&ctx, |ui| {
-                ui.label("Welcome!");
-                ui.label("Welcome!");
-                ui.label("Welcome!");
-                ui.label("Welcome!");
-                ui.label("Welcome!");
-                ui.label("Welcome!");
-                ui.label("Welcome!");
-                ui.label("Welcome!");
-                ui.label("Welcome!");
-                ui.label("Welcome!");
-                ui.label("Welcome!");
-                ui.label("Welcome!");
-                ui.label("Welcome!");
-                ui.label("Welcome!");
-                ui.label("Welcome!");
-                ui.label("Welcomeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeee!");
-
-                if ui.button("Press me").clicked() {
-                    println!("you pressed me!")
-                }
-                ui.checkbox(&mut checkbox1_checked, "checkbox1");
-                ui.end_row();
+            let now = running_time + frame_timer.elapsed_ms() as f64 / 1000.0;
+            input_state.process_and_run(&egui_ctx, &sys.sdl_window, Some(now), delta, &mut pending, |ctx| {
+                #[cfg(feature = "egui-demo")]
+                demo_windows.ui(&ctx);
+                egui::Window::new("Settings").resizable(true).vscroll(true).show(&ctx, |ui| {
+                    ui.label("Welcome!");
+                    ui.label("Welcome!");
+                    ui.label("Welcome!");
+                    ui.label("Welcome!");
+                    ui.label("Welcome!");
+                    ui.label("Welcome!");
+                    ui.label("Welcome!");
+                    ui.label("Welcome!");
+                    ui.label("Welcome!");
+                    ui.label("Welcome!");
+                    ui.label("Welcome!");
+                    ui.label("Welcome!");
+                    ui.label("Welcome!");
+                    ui.label("Welcome!");
+                    ui.label("Welcome!");
+                    ui.label("Welcomeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeeee!");
+
+                    if ui.button("Press me").clicked() {
+                        println!("you pressed me!")
+                    }
+                    ui.checkbox(&mut checkbox1_checked, "checkbox1");
+                    // Swapping fonts makes egui rebuild its font atlas and send the whole image again
+                    // as a `set` for the same texture id, which `update_textures` uploads before
+                    // the frame that first uses it is painted.
+                    if ui.checkbox(&mut monospace_ui, "Monospace UI font").changed() {
+                        ctx.set_fonts(ui_fonts(monospace_ui));
+                    }
+                    #[cfg(feature = "gamepad")]
+                    ui.label(format!("Connected controllers: {}", gamepads.connected().len()));
+                    #[cfg(feature = "gamepad")]
+                    if ui.button("Rumble").clicked() {
+                        for controller in gamepads.connected_mut() {
+                            if let Err(e) = gamepad::rumble(controller, 0.5, 0.5, Duration::from_millis(200)) {
+                                eprintln!("{}", e);
+                            }
+                        }
+                    }
+                    ui.checkbox(&mut run_egui_as_events_arrive, "Run egui as events arrive");
+                    #[cfg(feature = "input-latency")]
+                    match input_latency.average() {
+                        Some(avg) => ui.label(format!("Average input latency: {:.3} ms", avg.as_secs_f64() * 1000.0)),
+                        None => ui.label("Average input latency: -"),
+                    };
+                    #[cfg(feature = "input-latency")]
+                    if ui.button("Reset input latency").clicked() {
+                        input_latency.reset();
+                    }
+                    ui.end_row();
+                });
             });
-        });
+            #[cfg(feature = "input-latency")]
+            input_latency.frame_processed();
+            // Keep running egui on input as it arrives until `EVENT_WINDOW_MS` into the frame, then
+            // paint the merged output once.
+            if !run_egui_as_events_arrive {
+                break;
+            }
+            let remaining = EVENT_WINDOW_MS.saturating_sub(frame_timer.elapsed_ms());
+            if remaining == 0 || !wait_for_event(remaining) {
+                break;
+            }
+        }
+        let full_output = pending.expect("egui runs at least once per frame");
+
+        if input_state.take_render_reset() {
+            egui_rpass = Arc::new(RwLock::new(RenderPass::new(&sys.device, sys.surface_config.format, 1)));
+            texture_history.replay(&mut egui_rpass.write(), &sys.device, &sys.queue);
+        }
 
-        egui_sdl2_state.process_output(&sys.sdl_window, &full_output.platform_output);
+        if let Some((width, height)) = resize_debounce.take_settled() {
+            let config = &mut sys.surface_config;
+            config.width = width;
+            config.height = height;
+            sys.surface.configure(&sys.device, config);
+        }
+
+        texture_history.record(&full_output.textures_delta);
         let tris = egui_ctx.tessellate(full_output.shapes);
-        if (full_output.needs_repaint) {
-            paint_and_update_textures(&sys.device,
-                                      &sys.queue,
-                                      &sys.surface,
-                                      &sys.surface_config,
-                                      egui_rpass.clone(),
-                                      egui_sdl2_state.dpi_scaling,
-                                      Rgba::from_rgb(0.0, 0.0, 0.0),
-                                      &tris,
-                                      &full_output.textures_delta)
+        if full_output.needs_repaint && input_state.is_visible() {
+            let painted = paint_and_update_textures(&sys.device,
+                                                    &sys.queue,
+                                                    &sys.surface,
+                                                    &sys.surface_config,
+                                                    egui_rpass.clone(),
+                                                    input_state.pixels_per_point(),
+                                                    Rgba::from_rgb(0.0, 0.0, 0.0),
+                                                    &tris,
+                                                    &full_output.textures_delta);
+            if let Err(e) = painted {
+                eprintln!("Cannot acquire a frame from the surface: {}", e);
+                break 'running;
+            }
+        } else {
+            let mut rpass = egui_rpass.write();
+            update_textures(&sys.device, &sys.queue, &mut rpass, &full_output.textures_delta);
+            free_textures(&mut rpass, &full_output.textures_delta);
         }
         frame_timer.time_stop()
     }
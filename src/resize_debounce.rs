@@ -0,0 +1,31 @@
+use std::time::{Duration, Instant};
+
+// Collapses bursts of window resize events so the wgpu surface is reconfigured once the size has
+// stopped changing for `interval`. A zero interval reconfigures at most once per frame.
+pub struct ResizeDebounce {
+    interval: Duration,
+    pending: Option<(u32, u32)>,
+    last_resize: Instant,
+}
+
+impl ResizeDebounce {
+    pub fn new(interval: Duration) -> ResizeDebounce {
+        ResizeDebounce {
+            interval,
+            pending: None,
+            last_resize: Instant::now(),
+        }
+    }
+
+    pub fn resized(&mut self, width: u32, height: u32) {
+        self.pending = Some((width, height));
+        self.last_resize = Instant::now();
+    }
+
+    pub fn take_settled(&mut self) -> Option<(u32, u32)> {
+        if self.last_resize.elapsed() < self.interval {
+            return None;
+        }
+        self.pending.take()
+    }
+}
@@ -0,0 +1,75 @@
+// A clip rect in physical pixels, ready for `RenderPass::set_scissor_rect`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ScissorRect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+// Converts the clip rect of an egui `ClippedPrimitive`, which is in points, into physical pixels
+// of a target of `size_in_pixels`, with the same rounding and clamping egui-wgpu applies to its
+// own scissor rects, so custom rendering clips to exactly the pixels egui does. Returns `None`
+// where egui-wgpu would skip the primitive, since wgpu rejects empty scissor rects.
+#[allow(dead_code)]
+pub fn scissor_rect(clip_rect: egui::Rect, pixels_per_point: f32, size_in_pixels: [u32; 2]) -> Option<ScissorRect> {
+    let [target_width, target_height] = size_in_pixels;
+    let min_x = (clip_rect.min.x * pixels_per_point).clamp(0.0, target_width as f32);
+    let min_y = (clip_rect.min.y * pixels_per_point).clamp(0.0, target_height as f32);
+    let max_x = (clip_rect.max.x * pixels_per_point).clamp(min_x, target_width as f32);
+    let max_y = (clip_rect.max.y * pixels_per_point).clamp(min_y, target_height as f32);
+
+    let (min_x, min_y) = (min_x.round() as u32, min_y.round() as u32);
+    let (max_x, max_y) = (max_x.round() as u32, max_y.round() as u32);
+    // A clip rect thinner than a pixel still covers one.
+    let x = min_x.min(target_width);
+    let y = min_y.min(target_height);
+    let width = (max_x - min_x).max(1).min(target_width - x);
+    let height = (max_y - min_y).max(1).min(target_height - y);
+    if width == 0 || height == 0 {
+        return None;
+    }
+    Some(ScissorRect { x, y, width, height })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rect(min: [f32; 2], max: [f32; 2]) -> egui::Rect {
+        egui::Rect::from_min_max(min.into(), max.into())
+    }
+
+    #[test]
+    fn scales_clip_rect_to_pixels() {
+        let clip_rect = rect([10.0, 20.0], [110.0, 70.0]);
+        let cases = [
+            (1.0, ScissorRect { x: 10, y: 20, width: 100, height: 50 }),
+            (1.5, ScissorRect { x: 15, y: 30, width: 150, height: 75 }),
+            (2.0, ScissorRect { x: 20, y: 40, width: 200, height: 100 }),
+        ];
+        for (pixels_per_point, expected) in cases {
+            assert_eq!(scissor_rect(clip_rect, pixels_per_point, [1000, 1000]), Some(expected));
+        }
+    }
+
+    #[test]
+    fn rounds_fractional_pixels() {
+        let clip_rect = rect([1.3, 0.0], [10.1, 5.0]);
+        let expected = ScissorRect { x: 2, y: 0, width: 13, height: 8 };
+        assert_eq!(scissor_rect(clip_rect, 1.5, [100, 100]), Some(expected));
+    }
+
+    #[test]
+    fn clamps_at_target_edge() {
+        let clip_rect = rect([90.0, 70.0], [120.0, 100.0]);
+        let expected = ScissorRect { x: 90, y: 70, width: 10, height: 10 };
+        assert_eq!(scissor_rect(clip_rect, 1.0, [100, 80]), Some(expected));
+    }
+
+    #[test]
+    fn skips_clip_rect_outside_target() {
+        let clip_rect = rect([150.0, 10.0], [200.0, 20.0]);
+        assert_eq!(scissor_rect(clip_rect, 1.0, [100, 80]), None);
+    }
+}
@@ -0,0 +1,41 @@
+use std::collections::HashMap;
+use egui::epaint::ImageDelta;
+use egui::{TextureId, TexturesDelta};
+use egui_wgpu::renderer::RenderPass;
+use wgpu::{Device, Queue};
+
+// Remembers the texture updates egui sent since each texture was last set as a whole, so they
+// can be uploaded again into a new `RenderPass` after the GPU resources were lost. egui itself
+// only ever sends a texture once.
+pub struct TextureHistory {
+    deltas: HashMap<TextureId, Vec<ImageDelta>>,
+}
+
+impl TextureHistory {
+    pub fn new() -> TextureHistory {
+        TextureHistory {
+            deltas: HashMap::new(),
+        }
+    }
+
+    pub fn record(&mut self, textures_delta: &TexturesDelta) {
+        for (id, image_delta) in &textures_delta.set {
+            let history = self.deltas.entry(*id).or_default();
+            if image_delta.pos.is_none() {
+                history.clear();
+            }
+            history.push(image_delta.clone());
+        }
+        for id in &textures_delta.free {
+            self.deltas.remove(id);
+        }
+    }
+
+    pub fn replay(&self, rpass: &mut RenderPass, device: &Device, queue: &Queue) {
+        for (id, history) in &self.deltas {
+            for image_delta in history {
+                rpass.update_texture(device, queue, *id, image_delta);
+            }
+        }
+    }
+}
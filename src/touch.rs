@@ -0,0 +1,280 @@
+use egui::{Modifiers, PointerButton, Pos2, Rect, TouchDeviceId, TouchId, TouchPhase};
+use sdl2::event::Event;
+
+// `which` of mouse events SDL synthesizes from touch input.
+pub const SDL_TOUCH_MOUSEID: u32 = u32::MAX;
+
+// Translates SDL finger events into egui touches. egui 0.18 expects the integration to drive the
+// pointer from touch as well, so the first finger down also reports pointer move/press/release.
+pub struct TouchState {
+    primary_finger: Option<i64>,
+    // Where the primary finger went down, and whether it has since moved far enough to drag.
+    primary_start: Pos2,
+    primary_dragging: bool,
+}
+
+impl TouchState {
+    pub fn new() -> TouchState {
+        TouchState {
+            primary_finger: None,
+            primary_start: Pos2::ZERO,
+            primary_dragging: false,
+        }
+    }
+
+    // SDL reports finger positions normalized to 0..1 of the window, `window_rect` is where the
+    // window lies in egui's points; its origin is not at zero when egui only covers part of it.
+    // Returns false for events that aren't finger events. With `coalesce_motion`, a move of a
+    // finger whose last event this frame was also a move updates that event in place.
+    // The pointer only follows the primary finger once it has moved `tap_threshold` points from
+    // where it went down; until then jitter is swallowed and lifting the finger is a tap at the
+    // starting position. `Touch` events always carry the real positions.
+    pub fn on_event(
+        &mut self,
+        event: &Event,
+        window_rect: Rect,
+        modifiers: Modifiers,
+        coalesce_motion: bool,
+        tap_threshold: f32,
+        events: &mut Vec<egui::Event>,
+    ) -> bool {
+        let (touch_id, finger_id, x, y, pressure, phase) = match *event {
+            Event::FingerDown { touch_id, finger_id, x, y, pressure, .. } => {
+                (touch_id, finger_id, x, y, pressure, TouchPhase::Start)
+            }
+            Event::FingerMotion { touch_id, finger_id, x, y, pressure, .. } => {
+                (touch_id, finger_id, x, y, pressure, TouchPhase::Move)
+            }
+            Event::FingerUp { touch_id, finger_id, x, y, pressure, .. } => {
+                (touch_id, finger_id, x, y, pressure, TouchPhase::End)
+            }
+            _ => return false,
+        };
+        let pos = window_rect.min + window_rect.size() * egui::vec2(x, y);
+        let device_id = TouchDeviceId(touch_id as u64);
+        let id = TouchId(finger_id as u64);
+        // The primary finger is only coalesced once it drags, so the move that crosses the tap
+        // threshold isn't merged into a swallowed one.
+        let coalesce = coalesce_motion && (self.primary_finger != Some(finger_id) || self.primary_dragging);
+        if phase == TouchPhase::Move && coalesce && self.coalesce_move(device_id, id, pos, pressure, events) {
+            return true;
+        }
+        events.push(egui::Event::Touch {
+            device_id,
+            id,
+            phase,
+            pos,
+            force: pressure,
+        });
+
+        match phase {
+            TouchPhase::Start if self.primary_finger.is_none() => {
+                self.primary_finger = Some(finger_id);
+                self.primary_start = pos;
+                self.primary_dragging = false;
+                events.push(egui::Event::PointerMoved(pos));
+                events.push(egui::Event::PointerButton {
+                    pos,
+                    button: PointerButton::Primary,
+                    pressed: true,
+                    modifiers,
+                });
+            }
+            TouchPhase::Move if self.primary_finger == Some(finger_id) => {
+                if !self.primary_dragging && pos.distance(self.primary_start) < tap_threshold {
+                    return true;
+                }
+                self.primary_dragging = true;
+                events.push(egui::Event::PointerMoved(pos));
+            }
+            TouchPhase::End if self.primary_finger == Some(finger_id) => {
+                self.primary_finger = None;
+                let pos = if self.primary_dragging { pos } else { self.primary_start };
+                events.push(egui::Event::PointerButton {
+                    pos,
+                    button: PointerButton::Primary,
+                    pressed: false,
+                    modifiers,
+                });
+                events.push(egui::Event::PointerGone);
+            }
+            _ => {}
+        }
+        true
+    }
+
+    // Only the finger's most recent event is a candidate, so starts and ends are never merged
+    // away. The primary finger's pointer move is pushed right after its touch and moves with it.
+    fn coalesce_move(
+        &self,
+        device_id: TouchDeviceId,
+        id: TouchId,
+        pos: Pos2,
+        pressure: f32,
+        events: &mut [egui::Event],
+    ) -> bool {
+        let last = events.iter().rposition(|event| {
+            matches!(event, egui::Event::Touch { device_id: d, id: i, .. } if *d == device_id && *i == id)
+        });
+        let index = match last {
+            Some(index) => index,
+            None => return false,
+        };
+        match &mut events[index] {
+            egui::Event::Touch { phase: TouchPhase::Move, pos: last_pos, force, .. } => {
+                *last_pos = pos;
+                *force = pressure;
+            }
+            _ => return false,
+        }
+        if self.primary_finger == Some(id.0 as i64) {
+            if let Some(egui::Event::PointerMoved(pointer_pos)) = events.get_mut(index + 1) {
+                *pointer_pos = pos;
+            }
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    // Shared with the input tests, which feed fingers through `InputState`.
+    pub(crate) fn finger(phase: TouchPhase, finger_id: i64, x: f32, y: f32) -> Event {
+        let (timestamp, touch_id, dx, dy, pressure) = (0, 1, 0.0, 0.0, 1.0);
+        match phase {
+            TouchPhase::Start => Event::FingerDown { timestamp, touch_id, finger_id, x, y, dx, dy, pressure },
+            TouchPhase::Move => Event::FingerMotion { timestamp, touch_id, finger_id, x, y, dx, dy, pressure },
+            _ => Event::FingerUp { timestamp, touch_id, finger_id, x, y, dx, dy, pressure },
+        }
+    }
+
+    // Translates `fingers` onto a 100x100 point window.
+    fn translate(fingers: &[Event], coalesce_motion: bool, tap_threshold: f32) -> Vec<egui::Event> {
+        let window_rect = Rect::from_min_size(Pos2::ZERO, egui::vec2(100.0, 100.0));
+        let mut touch = TouchState::new();
+        let mut events = Vec::new();
+        for event in fingers {
+            assert!(touch.on_event(event, window_rect, Modifiers::default(), coalesce_motion, tap_threshold, &mut events));
+        }
+        events
+    }
+
+    fn touches(events: &[egui::Event]) -> Vec<(u64, TouchPhase, Pos2)> {
+        events
+            .iter()
+            .filter_map(|event| match event {
+                egui::Event::Touch { id, phase, pos, .. } => Some((id.0, *phase, *pos)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn motionless_tap_reports_start_and_end() {
+        let events = translate(
+            &[finger(TouchPhase::Start, 1, 0.5, 0.5), finger(TouchPhase::End, 1, 0.5, 0.5)],
+            false,
+            8.0,
+        );
+        let center = Pos2::new(50.0, 50.0);
+        assert_eq!(touches(&events), vec![(1, TouchPhase::Start, center), (1, TouchPhase::End, center)]);
+        assert!(events.contains(&egui::Event::PointerButton {
+            pos: center,
+            button: PointerButton::Primary,
+            pressed: false,
+            modifiers: Modifiers::default(),
+        }));
+    }
+
+    #[test]
+    fn coalesces_motion_per_finger() {
+        let fingers = [
+            finger(TouchPhase::Start, 1, 0.1, 0.1),
+            finger(TouchPhase::Start, 2, 0.9, 0.9),
+            finger(TouchPhase::Move, 1, 0.2, 0.2),
+            finger(TouchPhase::Move, 2, 0.8, 0.8),
+            finger(TouchPhase::Move, 1, 0.3, 0.3),
+            finger(TouchPhase::Move, 2, 0.7, 0.7),
+            finger(TouchPhase::End, 2, 0.7, 0.7),
+            finger(TouchPhase::Move, 1, 0.4, 0.4),
+        ];
+        let events = translate(&fingers, true, 0.0);
+        assert_eq!(
+            touches(&events),
+            vec![
+                (1, TouchPhase::Start, Pos2::new(10.0, 10.0)),
+                (2, TouchPhase::Start, Pos2::new(90.0, 90.0)),
+                (1, TouchPhase::Move, Pos2::new(40.0, 40.0)),
+                (2, TouchPhase::Move, Pos2::new(70.0, 70.0)),
+                (2, TouchPhase::End, Pos2::new(70.0, 70.0)),
+            ]
+        );
+        assert!(events.contains(&egui::Event::PointerMoved(Pos2::new(40.0, 40.0))));
+    }
+
+    #[test]
+    fn keeps_every_move_without_coalescing() {
+        let fingers = [
+            finger(TouchPhase::Start, 1, 0.1, 0.1),
+            finger(TouchPhase::Move, 1, 0.2, 0.2),
+            finger(TouchPhase::Move, 1, 0.3, 0.3),
+        ];
+        let moves = touches(&translate(&fingers, false, 0.0))
+            .into_iter()
+            .filter(|(_, phase, _)| *phase == TouchPhase::Move)
+            .count();
+        assert_eq!(moves, 2);
+    }
+
+    fn pointer_events(events: &[egui::Event]) -> Vec<egui::Event> {
+        events
+            .iter()
+            .filter(|event| matches!(event, egui::Event::PointerMoved(_) | egui::Event::PointerButton { .. }))
+            .cloned()
+            .collect()
+    }
+
+    fn primary(pos: Pos2, pressed: bool) -> egui::Event {
+        egui::Event::PointerButton {
+            pos,
+            button: PointerButton::Primary,
+            pressed,
+            modifiers: Modifiers::default(),
+        }
+    }
+
+    #[test]
+    fn jitter_below_tap_threshold_stays_a_tap() {
+        let fingers = [
+            finger(TouchPhase::Start, 1, 0.5, 0.5),
+            finger(TouchPhase::Move, 1, 0.55, 0.5),
+            finger(TouchPhase::End, 1, 0.55, 0.5),
+        ];
+        let start = Pos2::new(50.0, 50.0);
+        assert_eq!(
+            pointer_events(&translate(&fingers, false, 10.0)),
+            vec![egui::Event::PointerMoved(start), primary(start, true), primary(start, false)]
+        );
+    }
+
+    #[test]
+    fn moving_past_tap_threshold_drags() {
+        let fingers = [
+            finger(TouchPhase::Start, 1, 0.5, 0.5),
+            finger(TouchPhase::Move, 1, 0.75, 0.5),
+            finger(TouchPhase::End, 1, 0.75, 0.5),
+        ];
+        let (start, end) = (Pos2::new(50.0, 50.0), Pos2::new(75.0, 50.0));
+        assert_eq!(
+            pointer_events(&translate(&fingers, false, 10.0)),
+            vec![
+                egui::Event::PointerMoved(start),
+                primary(start, true),
+                egui::Event::PointerMoved(end),
+                primary(end, false),
+            ]
+        );
+    }
+}